@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+
+use crate::error::ParseError;
+
+/// Detect a BGZF (block gzip) stream: a gzip member whose extra field carries a "BC"
+/// subfield recording the compressed size of the block (see the SAM/BAM spec, section 4.1).
+pub fn is_bgzf(path: &str) -> Result<bool> {
+    let mut file = File::open(path).with_context(|| format!("failed to open file {}", path))?;
+    let mut header = [0u8; 18];
+    if file.read(&mut header)? < 18 {
+        return Ok(false);
+    }
+    Ok(header[0] == 0x1f
+        && header[1] == 0x8b
+        && header[3] & 0x04 != 0 // FEXTRA flag
+        && &header[12..14] == b"BC")
+}
+
+/// The size, in bytes, of one BGZF block starting at the current file position, read from
+/// its "BC" extra-field subfield (`BSIZE`, the total block size minus one).
+fn bgzf_block_size(file: &mut File) -> Result<Option<u64>> {
+    let start = file.stream_position()?;
+    let mut header = [0u8; 18];
+    let read = file.read(&mut header)?;
+    if read == 0 {
+        return Ok(None); // clean EOF
+    }
+    if read < 18 || header[0] != 0x1f || header[1] != 0x8b || &header[12..14] != b"BC" {
+        return Err(ParseError::new(format!(
+            "Not a valid BGZF block at offset {}",
+            start
+        ))
+        .into());
+    }
+    let bsize = u16::from_le_bytes([header[16], header[17]]) as u64 + 1;
+    file.seek(SeekFrom::Start(start))?;
+    Ok(Some(bsize))
+}
+
+/// Decompress the single BGZF block starting at `coffset` in `file`, returning its
+/// uncompressed bytes.
+fn read_bgzf_block(file: &mut File, coffset: u64) -> Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(coffset))?;
+    let block_size = bgzf_block_size(file)?
+        .with_context(|| format!("BGZF block at offset {} is missing or truncated", coffset))?;
+    let mut compressed = vec![0u8; block_size as usize];
+    file.read_exact(&mut compressed)?;
+    let mut decompressed = Vec::new();
+    GzDecoder::new(Cursor::new(compressed)).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// A parsed `.tbi` tabix index (samtools tabix format): for each reference sequence, a
+/// binning index (bin id -> chunks of BGZF virtual offsets) plus a linear index used to
+/// skip chunks that cannot overlap the query.
+pub struct TabixIndex {
+    references: HashMap<String, ReferenceIndex>,
+}
+
+struct ReferenceIndex {
+    bins: HashMap<u32, Vec<(u64, u64)>>,
+    linear_index: Vec<u64>,
+}
+
+const TABIX_LINEAR_SHIFT: u32 = 14;
+
+impl TabixIndex {
+    pub fn open(tbi_path: &str) -> Result<Self> {
+        let file = File::open(tbi_path)
+            .with_context(|| format!("failed to open tabix index {}", tbi_path))?;
+        let mut data = Vec::new();
+        GzDecoder::new(file).read_to_end(&mut data)?;
+        let mut cursor = Cursor::new(data);
+
+        let mut magic = [0u8; 4];
+        cursor.read_exact(&mut magic)?;
+        if &magic != b"TBI\x01" {
+            return Err(ParseError::new(format!("{} is not a tabix index", tbi_path)).into());
+        }
+
+        let n_ref = read_i32(&mut cursor)?;
+        let _format = read_i32(&mut cursor)?;
+        let _col_seq = read_i32(&mut cursor)?;
+        let _col_beg = read_i32(&mut cursor)?;
+        let _col_end = read_i32(&mut cursor)?;
+        let _meta = read_i32(&mut cursor)?;
+        let _skip = read_i32(&mut cursor)?;
+        let l_nm = read_i32(&mut cursor)?;
+        let mut names_buf = vec![0u8; l_nm as usize];
+        cursor.read_exact(&mut names_buf)?;
+        let names: Vec<String> = names_buf
+            .split(|&b| b == 0)
+            .filter(|s| !s.is_empty())
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .collect();
+
+        let mut references = HashMap::new();
+        for name in names.into_iter().take(n_ref as usize) {
+            let n_bin = read_i32(&mut cursor)?;
+            let mut bins = HashMap::new();
+            for _ in 0..n_bin {
+                let bin = read_u32(&mut cursor)?;
+                let n_chunk = read_i32(&mut cursor)?;
+                let mut chunks = Vec::with_capacity(n_chunk as usize);
+                for _ in 0..n_chunk {
+                    let chunk_beg = read_u64(&mut cursor)?;
+                    let chunk_end = read_u64(&mut cursor)?;
+                    chunks.push((chunk_beg, chunk_end));
+                }
+                bins.insert(bin, chunks);
+            }
+            let n_intv = read_i32(&mut cursor)?;
+            let mut linear_index = Vec::with_capacity(n_intv as usize);
+            for _ in 0..n_intv {
+                linear_index.push(read_u64(&mut cursor)?);
+            }
+            references.insert(name, ReferenceIndex { bins, linear_index });
+        }
+
+        Ok(Self { references })
+    }
+
+    /// Chunks (BGZF virtual offset ranges) that may contain records overlapping
+    /// `[start, stop)` on `chr`, per the standard BAI/CSI binning + linear-index scheme.
+    fn candidate_chunks(&self, chr: &str, start: usize, stop: usize) -> Option<Vec<(u64, u64)>> {
+        let reference = self.references.get(chr)?;
+        let min_offset = reference
+            .linear_index
+            .get(start >> TABIX_LINEAR_SHIFT)
+            .copied()
+            .unwrap_or(0);
+
+        let mut chunks: Vec<(u64, u64)> = reg2bins(start as i64, stop as i64)
+            .into_iter()
+            .filter_map(|bin| reference.bins.get(&bin))
+            .flatten()
+            .copied()
+            .filter(|(_, chunk_end)| *chunk_end > min_offset)
+            .collect();
+        chunks.sort_unstable();
+        Some(chunks)
+    }
+}
+
+/// The standard UCSC/BAI binning scheme: which bins can contain features overlapping
+/// `[beg, end)`, across the six bin levels (16kbp..512Mbp).
+fn reg2bins(beg: i64, end: i64) -> Vec<u32> {
+    let end = (end - 1).max(beg);
+    let mut bins = vec![0u32];
+    for k in (1 + (beg >> 26))..=(1 + (end >> 26)) {
+        bins.push(k as u32);
+    }
+    for k in (9 + (beg >> 23))..=(9 + (end >> 23)) {
+        bins.push(k as u32);
+    }
+    for k in (73 + (beg >> 20))..=(73 + (end >> 20)) {
+        bins.push(k as u32);
+    }
+    for k in (585 + (beg >> 17))..=(585 + (end >> 17)) {
+        bins.push(k as u32);
+    }
+    for k in (4681 + (beg >> 14))..=(4681 + (end >> 14)) {
+        bins.push(k as u32);
+    }
+    bins
+}
+
+fn read_i32(cursor: &mut Cursor<Vec<u8>>) -> Result<i32> {
+    let mut buf = [0u8; 4];
+    cursor.read_exact(&mut buf)?;
+    Ok(i32::from_le_bytes(buf))
+}
+
+fn read_u32(cursor: &mut Cursor<Vec<u8>>) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    cursor.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(cursor: &mut Cursor<Vec<u8>>) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    cursor.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Use a `.tbi` tabix index beside `path` to decompress only the BGZF blocks overlapping
+/// `[start, stop)` on `chr`, instead of scanning the whole (coordinate-sorted) file.
+pub fn get_region_reader(path: &str, chr: &str, start: usize, stop: usize) -> Result<Box<dyn Read>> {
+    let tbi_path = format!("{}.tbi", path);
+    let index = TabixIndex::open(&tbi_path)?;
+    let chunks = index
+        .candidate_chunks(chr, start, stop)
+        .with_context(|| format!("{} has no entries for contig {}", tbi_path, chr))?;
+
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open bgzf file {}", path))?;
+    let mut decompressed = Vec::new();
+    for (chunk_beg, chunk_end) in merge_chunks(chunks) {
+        let mut coffset = chunk_beg >> 16;
+        let end_coffset = chunk_end >> 16;
+        let end_uoffset = (chunk_end & 0xffff) as usize;
+        loop {
+            let block = read_bgzf_block(&mut file, coffset)?;
+            let block_uoffset = if coffset == chunk_beg >> 16 {
+                (chunk_beg & 0xffff) as usize
+            } else {
+                0
+            };
+            if coffset >= end_coffset {
+                decompressed.extend_from_slice(&block[block_uoffset..end_uoffset.min(block.len())]);
+                break;
+            }
+            decompressed.extend_from_slice(&block[block_uoffset..]);
+            coffset = file.stream_position()?;
+        }
+    }
+    Ok(Box::new(Cursor::new(decompressed)))
+}
+
+/// Coalesce overlapping/adjacent chunks so each BGZF block is decompressed at most once.
+fn merge_chunks(chunks: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (beg, end) in chunks {
+        if let Some(last) = merged.last_mut() {
+            if beg <= last.1 {
+                last.1 = last.1.max(end);
+                continue;
+            }
+        }
+        merged.push((beg, end));
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reg2bins_smallest_region() {
+        // [0, 0) touches exactly one bin at each of the six levels.
+        assert_eq!(reg2bins(0, 0), vec![0, 1, 9, 73, 585, 4681]);
+    }
+
+    #[test]
+    fn test_reg2bins_spans_multiple_bins_at_finer_levels() {
+        let bins = reg2bins(100_000, 200_000);
+        // the region is small enough to stay within a single bin at the four coarsest
+        // levels, but straddles a bin boundary at the two finest levels
+        assert_eq!(bins[..4], [0, 1, 9, 73]);
+        assert_eq!(&bins[4..6], &[585, 586]);
+        assert_eq!(&bins[6..], &[4687, 4688, 4689, 4690, 4691, 4692, 4693]);
+    }
+
+    #[test]
+    fn test_merge_chunks_coalesces_overlapping_and_touching() {
+        let merged = merge_chunks(vec![(0, 10), (5, 15), (20, 30), (30, 40)]);
+        assert_eq!(merged, vec![(0, 15), (20, 40)]);
+    }
+
+    #[test]
+    fn test_merge_chunks_keeps_disjoint_chunks_separate() {
+        let merged = merge_chunks(vec![(0, 5), (10, 15)]);
+        assert_eq!(merged, vec![(0, 5), (10, 15)]);
+    }
+}
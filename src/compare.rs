@@ -4,14 +4,16 @@ use std::collections::HashMap;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::counts::{ExpectedMutationCounts, ObservedMutationCounts};
+use mutexpect::MutationEvent;
+
+use crate::counts::{ExpectedMutationCounts, ObservedMutationCounts, PValues};
 use crate::io::get_writer;
-use crate::observed::AnnotatedPointMutation;
+use crate::observed::Mutation;
 use crate::sample::SampledMutations;
 use crate::{Float, MutationType};
 
 pub fn compare_mutations(
-    classified_observed_mutations: &[AnnotatedPointMutation],
+    classified_observed_mutations: &[Mutation],
     expected_mutations: &HashMap<String, ExpectedMutationCounts>,
     sampled_mutations: &SampledMutations,
     filter_for_id: Option<&str>,
@@ -47,23 +49,89 @@ pub fn compare_mutations(
             result.push(comparison);
         }
     }
+    if filter_for_id.is_none() {
+        // only a whole-cohort run has the full set of p-values a correction needs;
+        // --id-restricted runs leave q_value/bonferroni equal to p_value (see --action fdr)
+        apply_fdr_correction(&mut result);
+    }
+    result.sort_unstable_by(|a, b| a.p_value.partial_cmp(&b.p_value).unwrap_or(Equal));
+    Ok(result)
+}
+
+/// Analytic counterpart to `compare_mutations`, for `--action analytic`: instead of a
+/// Monte-Carlo p-value looked up from `sampled_mutations`, compute the exact Poisson-binomial
+/// p-value directly from the per-site probabilities in `possible_mutations`. This has no
+/// sampling-resolution floor and needs no `sampled-mutations` replicates at all.
+pub fn compare_mutations_analytic(
+    classified_observed_mutations: &[Mutation],
+    expected_mutations: &HashMap<String, ExpectedMutationCounts>,
+    possible_mutations: &HashMap<String, Vec<MutationEvent>>,
+    filter_for_id: Option<&str>,
+) -> Result<Vec<ComparedMutations>> {
+    let mut result = vec![];
+    let observed_mutations =
+        tally_up_observed_mutations(classified_observed_mutations, filter_for_id);
+
+    let no_observations = ObservedMutationCounts::default();
+    for (region, region_expected) in expected_mutations {
+        if let Some(id) = filter_for_id {
+            if region != id {
+                continue;
+            }
+        }
+        let region_observed = observed_mutations.get(region).unwrap_or(&no_observations);
+        let region_possible = possible_mutations.get(region).with_context(|| {
+            format!("Failed to look up possible mutations for region {}", &region)
+        })?;
+
+        for mutation_type in MutationType::iter() {
+            if mutation_type == MutationType::Unknown {
+                continue;
+            }
+            let expected = region_expected.get(mutation_type);
+            let observed = region_observed.get(mutation_type);
+            let site_probabilities: Vec<Float> = region_possible
+                .iter()
+                .filter(|event| event.mutation_type == mutation_type)
+                .map(|event| event.probability)
+                .collect();
+            if site_probabilities.is_empty() {
+                if !expected.eq(&0.0) {
+                    eprintln!("[WARNING] mutation_type={} has no possible sites but an expectation value of {} in region {}", mutation_type, expected, region);
+                }
+                continue; // will not add to result
+            }
+            let p_value =
+                PValues::from_poisson_binomial(&site_probabilities).n_hits_or_more(observed);
+            let comparison =
+                ComparedMutations::new(region.clone(), mutation_type, observed, expected, p_value);
+            result.push(comparison);
+        }
+    }
+    if filter_for_id.is_none() {
+        apply_fdr_correction(&mut result);
+    }
     result.sort_unstable_by(|a, b| a.p_value.partial_cmp(&b.p_value).unwrap_or(Equal));
     Ok(result)
 }
 
 pub fn tally_up_observed_mutations(
-    mutations: &[AnnotatedPointMutation],
+    mutations: &[Mutation],
     filter_for_id: Option<&str>,
 ) -> HashMap<String, ObservedMutationCounts> {
     let mut result = HashMap::new();
     for mutation in mutations {
+        let region = match &mutation.region {
+            Some(region) => region,
+            None => continue, // could not be assigned to any annotation
+        };
         if let Some(id) = filter_for_id {
-            if mutation.region_name != id {
+            if region != id {
                 continue;
             }
         }
         result
-            .entry(mutation.region_name.clone())
+            .entry(region.clone())
             .or_insert_with(ObservedMutationCounts::default)
             .add(mutation.mutation_type, 1);
     }
@@ -73,10 +141,12 @@ pub fn tally_up_observed_mutations(
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ComparedMutations {
     region: String,
-    mutation_type: &'static str, // I want a string representation in the output file
+    mutation_type: String, // a string representation in the output file; owned so rows round-trip through read_from_file
     observed: usize,
     expected: Float,
     p_value: Float,
+    q_value: Float,    // Benjamini-Hochberg FDR-adjusted p-value; equals p_value until `apply_fdr_correction` runs
+    bonferroni: Float, // Bonferroni-adjusted p-value; equals p_value until `apply_fdr_correction` runs
 }
 
 impl ComparedMutations {
@@ -92,11 +162,44 @@ impl ComparedMutations {
             observed,
             expected,
             p_value,
-            mutation_type: mutation_type.as_str(),
+            q_value: p_value,
+            bonferroni: p_value,
+            mutation_type: mutation_type.as_str().to_string(),
         }
     }
 }
 
+/// Benjamini-Hochberg FDR q-values and Bonferroni-adjusted p-values, computed over every
+/// p-value in `compared_mutations` together. Run this whenever the p-values being written
+/// span the whole cohort (no `--id`), since leaving thousands of per-transcript p-values
+/// uncorrected overstates how many "significant" genes are real.
+pub fn apply_fdr_correction(compared_mutations: &mut [ComparedMutations]) {
+    let test_count = compared_mutations.len();
+    if test_count == 0 {
+        return;
+    }
+
+    let mut by_p_value: Vec<usize> = (0..test_count).collect();
+    by_p_value.sort_unstable_by(|&a, &b| {
+        compared_mutations[a]
+            .p_value
+            .partial_cmp(&compared_mutations[b].p_value)
+            .unwrap_or(Equal)
+    });
+
+    // Benjamini-Hochberg: q_(i) = p_(i) * m / rank(i), then enforce monotonicity by walking
+    // from the largest p-value down so q-values never decrease as p-values increase.
+    let mut running_min = 1.0;
+    for (position, &index) in by_p_value.iter().enumerate().rev() {
+        let rank = (position + 1) as Float; // 1-based rank among sorted p-values
+        let p_value = compared_mutations[index].p_value;
+        let q_value = (p_value * test_count as Float / rank).min(1.0);
+        running_min = running_min.min(q_value);
+        compared_mutations[index].q_value = running_min;
+        compared_mutations[index].bonferroni = (p_value * test_count as Float).min(1.0);
+    }
+}
+
 pub fn write_to_file(out_path: &str, compared_mutations: &[ComparedMutations]) -> Result<()> {
     let writer = get_writer(out_path)
         .with_context(|| format!("failed to open file {} for writing", out_path))?;
@@ -109,5 +212,58 @@ pub fn write_to_file(out_path: &str, compared_mutations: &[ComparedMutations]) -
     Ok(())
 }
 
+pub fn read_from_file(in_path: &str) -> Result<Vec<ComparedMutations>> {
+    let reader = get_reader(in_path)
+        .with_context(|| format!("failed to open file {} for reading", in_path))?;
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .from_reader(reader);
+    let mut result = Vec::new();
+    for row_result in csv_reader.deserialize() {
+        let row: ComparedMutations = row_result?;
+        result.push(row);
+    }
+    Ok(result)
+}
+
+/// Standalone counterpart to the FDR pass embedded in `compare_mutations`, for `--action
+/// fdr`: pools the `ComparedMutations` rows from one or more `significant-mutations` files
+/// (as written by separate `--id`-restricted runs) and rewrites all of them with q-values
+/// computed over the whole pooled set, so distributed per-gene runs can be reconciled into
+/// a single corrected table.
+pub fn reconcile_significant_mutations(input_paths: &[&str]) -> Result<Vec<ComparedMutations>> {
+    let mut pooled = Vec::new();
+    for path in input_paths {
+        pooled.extend(read_from_file(path)?);
+    }
+    apply_fdr_correction(&mut pooled);
+    pooled.sort_unstable_by(|a, b| a.p_value.partial_cmp(&b.p_value).unwrap_or(Equal));
+    Ok(pooled)
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fdr_correction_matches_benjamini_hochberg() {
+        // canonical textbook example: 5 p-values with a known BH outcome
+        let p_values = [0.01, 0.02, 0.03, 0.04, 0.20];
+        let mut compared: Vec<ComparedMutations> = p_values
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| {
+                ComparedMutations::new(format!("gene{}", i), MutationType::Missense, 1, 1.0, p)
+            })
+            .collect();
+
+        apply_fdr_correction(&mut compared);
+
+        let q_values: Vec<Float> = compared.iter().map(|c| c.q_value).collect();
+        assert!((q_values[0] - 0.05).abs() < 1e-6);
+        assert!((q_values[1] - 0.05).abs() < 1e-6);
+        assert!((q_values[2] - 0.05).abs() < 1e-6);
+        assert!((q_values[3] - 0.05).abs() < 1e-6);
+        assert!((q_values[4] - 0.20).abs() < 1e-6);
+    }
+}
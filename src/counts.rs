@@ -164,6 +164,172 @@ impl PValues {
     pub fn n_hits_or_more(&self, n: usize) -> Float {
         self.p_values.get(n).copied().unwrap_or(0.0)
     }
+
+    /// Exact Poisson-binomial right-tail p-values: `probabilities[i]` is the success
+    /// probability of an independent Bernoulli site `i`, and `n_hits_or_more(k)` on the
+    /// result is `P(sum_i Bernoulli(p_i) >= k)`. Unlike `DefaultCounter::p_values`, this has
+    /// no sampling-resolution floor and needs no `sampled-mutations` replicates at all.
+    pub fn from_poisson_binomial(probabilities: &[Float]) -> PValues {
+        if probabilities.len() > POISSON_BINOMIAL_DFT_THRESHOLD {
+            poisson_binomial_distribution_via_dft(probabilities)
+        } else {
+            poisson_binomial_distribution_via_dp(probabilities)
+        }
+    }
+}
+
+/// Above this many sites, the O(n^2) DP below gets slow and its `f32` accumulation starts
+/// losing precision in the distribution's tails; switch to the DFT-based method instead.
+const POISSON_BINOMIAL_DFT_THRESHOLD: usize = 500;
+
+/// The textbook Poisson-binomial DP: `dist[k]` is `P(sum of the sites seen so far == k)`.
+/// Each site with success probability `p` is folded in from high index to low so that
+/// `dist[k-1]` (a not-yet-updated, "previous site" value) is still available when `dist[k]`
+/// is computed.
+fn poisson_binomial_distribution_via_dp(probabilities: &[Float]) -> PValues {
+    let n = probabilities.len();
+    let mut dist = vec![0.0 as Float; n + 1];
+    dist[0] = 1.0;
+    for &p in probabilities {
+        for k in (1..=n).rev() {
+            dist[k] = dist[k] * (1.0 - p) + dist[k - 1] * p;
+        }
+        dist[0] *= 1.0 - p;
+    }
+    distribution_to_right_tail_p_values(&dist)
+}
+
+/// Hong's characteristic-function method: the distribution of `sum_i Bernoulli(p_i)` is the
+/// forward DFT, divided by `m`, of `phi(t) = prod_i (1 - p_i + p_i * e^{i*2*pi*t/m})` evaluated on a grid of
+/// `m = (n+1).next_power_of_two()` points (padded with zero-probability dummy sites, which
+/// leaves the distribution unchanged but gives the FFT a power-of-two length). Accumulated
+/// in `f64` throughout to avoid the DP's precision loss for large `n`.
+fn poisson_binomial_distribution_via_dft(probabilities: &[Float]) -> PValues {
+    let n = probabilities.len();
+    let support_size = n + 1;
+    let padded_size = support_size.next_power_of_two();
+
+    let padded_probabilities: Vec<f64> = probabilities
+        .iter()
+        .map(|&p| p as f64)
+        .chain(std::iter::repeat(0.0).take(padded_size - n))
+        .collect();
+
+    let mut spectrum = vec![Complex::new(0.0, 0.0); padded_size];
+    for (t, value) in spectrum.iter_mut().enumerate() {
+        let angle = 2.0 * std::f64::consts::PI * t as f64 / padded_size as f64;
+        let omega = Complex::new(angle.cos(), angle.sin());
+        let mut product = Complex::new(1.0, 0.0);
+        for &p in &padded_probabilities {
+            product = product * Complex::new(1.0 - p, 0.0).add(omega.scale(p));
+        }
+        *value = product;
+    }
+    // `spectrum[t]` was built as phi(t) = sum_k pmf[k] * e^{+i*2*pi*k*t/padded_size}, i.e. an
+    // *unnormalized inverse* DFT of the pmf. Recovering the pmf is therefore a forward DFT
+    // of `spectrum` divided by `padded_size` -- not another inverse transform, which would
+    // apply the same `+i` twiddle sign twice and come back scrambled.
+    fft(&mut spectrum, false);
+    let padded_size_f = padded_size as f64;
+
+    let pmf: Vec<Float> = spectrum[..support_size]
+        .iter()
+        .map(|c| (c.re / padded_size_f).max(0.0) as Float)
+        .collect();
+    distribution_to_right_tail_p_values(&pmf)
+}
+
+fn distribution_to_right_tail_p_values(distribution: &[Float]) -> PValues {
+    let mut result = vec![0.0 as Float; distribution.len()];
+    let mut accumulator: Float = 0.0;
+    for (k, &p) in distribution.iter().enumerate().rev() {
+        accumulator += p;
+        result[k] = accumulator;
+    }
+    PValues { p_values: result }
+}
+
+#[derive(Copy, Clone)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+
+    fn scale(self, factor: f64) -> Complex {
+        Complex::new(self.re * factor, self.im * factor)
+    }
+}
+
+impl std::ops::Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// In-place iterative radix-2 Cooley-Tukey FFT. `data.len()` must be a power of two.
+/// `inverse` selects the sign of the twiddle factors and normalizes by `1/len` at the end.
+fn fft(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+    if n <= 1 {
+        return;
+    }
+
+    // bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j ^= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle_sign = if inverse { 1.0 } else { -1.0 };
+        let angle = angle_sign * 2.0 * std::f64::consts::PI / len as f64;
+        let step = Complex::new(angle.cos(), angle.sin());
+        let mut start = 0;
+        while start < n {
+            let mut twiddle = Complex::new(1.0, 0.0);
+            for k in 0..len / 2 {
+                let u = data[start + k];
+                let v = data[start + k + len / 2] * twiddle;
+                data[start + k] = u.add(v);
+                data[start + k + len / 2] = u.sub(v);
+                twiddle = twiddle * step;
+            }
+            start += len;
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        for value in data.iter_mut() {
+            *value = value.scale(1.0 / n as f64);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -195,4 +361,72 @@ mod tests {
         assert_eq!(p_values.n_hits_or_more(9), 39.0 / 100.0);
         assert_eq!(p_values.n_hits_or_more(10), 0.0);
     }
+
+    // brute-force: enumerate all 2^n outcomes and bucket them by number of successes
+    fn brute_force_distribution(probabilities: &[Float]) -> Vec<Float> {
+        let n = probabilities.len();
+        let mut distribution = vec![0.0 as Float; n + 1];
+        for outcome in 0..(1u32 << n) {
+            let mut probability = 1.0;
+            let mut successes = 0;
+            for (i, &p) in probabilities.iter().enumerate() {
+                if outcome & (1 << i) != 0 {
+                    probability *= p;
+                    successes += 1;
+                } else {
+                    probability *= 1.0 - p;
+                }
+            }
+            distribution[successes] += probability;
+        }
+        distribution
+    }
+
+    // recover the per-k probability mass from the cumulative right-tail p-values, so a test
+    // can compare the whole distribution without a test-only accessor on `PValues`
+    fn pmf_from_p_values(p_values: &PValues, n: usize) -> Vec<Float> {
+        (0..=n)
+            .map(|k| p_values.n_hits_or_more(k) - p_values.n_hits_or_more(k + 1))
+            .collect()
+    }
+
+    #[test]
+    fn test_poisson_binomial_matches_brute_force() {
+        let probabilities: Vec<Float> = vec![0.2, 0.5, 0.9, 0.05];
+        let n = probabilities.len();
+
+        let brute_force = brute_force_distribution(&probabilities);
+        let expected = distribution_to_right_tail_p_values(&brute_force);
+
+        let via_dp = poisson_binomial_distribution_via_dp(&probabilities);
+        let via_dft = poisson_binomial_distribution_via_dft(&probabilities);
+        for k in 0..=n {
+            assert!((via_dp.n_hits_or_more(k) - expected.n_hits_or_more(k)).abs() < 1e-4);
+            assert!((via_dft.n_hits_or_more(k) - expected.n_hits_or_more(k)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_poisson_binomial_dft_matches_brute_force_pmf() {
+        // n=5 (padded to the next power of two, 8) so the padding dummy sites are
+        // exercised too; checked index-by-index against the brute-force PMF rather than
+        // only the cumulative right-tail p-values, since a scrambled/index-reversed PMF
+        // can still satisfy the two boundary checks (k=0 and k=n) by coincidence.
+        let probabilities: Vec<Float> = vec![0.2, 0.5, 0.9, 0.9, 0.05];
+        let n = probabilities.len();
+
+        let brute_force = brute_force_distribution(&probabilities);
+        let via_dft = poisson_binomial_distribution_via_dft(&probabilities);
+        let pmf = pmf_from_p_values(&via_dft, n);
+
+        for k in 0..=n {
+            assert!(
+                (pmf[k] - brute_force[k]).abs() < 1e-4,
+                "pmf[{}] = {}, expected {}",
+                k,
+                pmf[k],
+                brute_force[k]
+            );
+        }
+    }
 }
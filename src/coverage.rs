@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+
+use mutexpect::{Interval, SeqAnnotation};
+use rust_htslib::bam::{self, Read as BamRead};
+
+// Untested: exercising this requires a real indexed BAM fixture, and unlike the FASTA
+// reader elsewhere in this module family, rust_htslib's BAM/BAI format has no hand-writable
+// text form to construct one from inline in a unit test.
+/// Compute, for each annotation, the fraction of each of its CDS regions covered by at least
+/// `min_depth` aligned reads in `bam_path` (one fraction per `annotation.coding_sequences`
+/// entry, in that order; annotations with no CDS get a single fraction over their whole
+/// `range`). Used by `enumerate::enumerate_possible_mutations` to scale down the PaPa-derived
+/// mutation probabilities CDS-by-CDS, so a fully-covered CDS isn't dragged down by a poorly
+/// sequenced exon elsewhere in the same transcript.
+pub fn callable_fraction_by_region(
+    bam_path: &str,
+    annotations: &[SeqAnnotation],
+    min_depth: u32,
+) -> Result<HashMap<String, Vec<f32>>> {
+    let mut reader = bam::IndexedReader::from_path(bam_path)
+        .with_context(|| format!("failed to open indexed alignment file {}", bam_path))?;
+
+    let mut result = HashMap::with_capacity(annotations.len());
+    for annotation in annotations {
+        let tid = reader
+            .header()
+            .tid(annotation.chr.as_bytes())
+            .with_context(|| format!("contig {} not found in {}", annotation.chr, bam_path))?;
+
+        let regions: Vec<Interval> = if annotation.coding_sequences.is_empty() {
+            vec![annotation.range.clone()]
+        } else {
+            annotation.coding_sequences.iter().map(|cds| cds.range.clone()).collect()
+        };
+
+        let mut fractions = Vec::with_capacity(regions.len());
+        for region in &regions {
+            reader
+                .fetch((tid, region.start as i64, region.stop as i64))
+                .with_context(|| {
+                    format!(
+                        "failed to fetch {}:{}-{} from {}",
+                        annotation.chr, region.start, region.stop, bam_path
+                    )
+                })?;
+
+            let mut callable_positions = 0usize;
+            for pileup_result in reader.pileup() {
+                let pileup = pileup_result?;
+                let pos = pileup.pos() as usize;
+                if pos < region.start || pos >= region.stop {
+                    continue; // the pileup iterator can spill a little past the fetched window
+                }
+                if pileup.depth() >= min_depth {
+                    callable_positions += 1;
+                }
+            }
+            fractions.push(callable_positions as f32 / region.len() as f32);
+        }
+
+        result.insert(annotation.name.clone(), fractions);
+    }
+    Ok(result)
+}
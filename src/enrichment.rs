@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use mutexpect::SeqAnnotation;
+
+use crate::counts::{ExpectedMutationCounts, ObservedMutationCounts};
+use crate::io::get_writer;
+use crate::observed::{classify_mutations, read_mutations_from_vcf, Mutation};
+use crate::reference::ReferenceSource;
+use crate::{Float, MutationType};
+
+fn tally(mutations: &[Mutation], filter_for_id: Option<&str>) -> HashMap<String, ObservedMutationCounts> {
+    let mut result = HashMap::new();
+    for mutation in mutations {
+        let region = match &mutation.region {
+            Some(region) => region,
+            None => continue, // could not be assigned to any annotation
+        };
+        if let Some(id) = filter_for_id {
+            if region != id {
+                continue;
+            }
+        }
+        result
+            .entry(region.clone())
+            .or_insert_with(ObservedMutationCounts::default)
+            .add(mutation.mutation_type, 1);
+    }
+    result
+}
+
+/// The Poisson probability mass function, computed iteratively to avoid factorial overflow.
+fn poisson_pmf(k: usize, lambda: f64) -> f64 {
+    let mut pmf = (-lambda).exp();
+    for i in 1..=k {
+        pmf *= lambda / i as f64;
+    }
+    pmf
+}
+
+fn poisson_cdf(k: usize, lambda: f64) -> f64 {
+    (0..=k).map(|i| poisson_pmf(i, lambda)).sum()
+}
+
+/// Two-sided Poisson p-value for observing `k` events given mean `lambda`: twice the smaller
+/// of the lower and upper tail probabilities, capped at 1.0.
+fn poisson_two_sided_p_value(k: usize, lambda: Float) -> Float {
+    let lambda = lambda as f64;
+    if lambda <= 0.0 {
+        return if k == 0 { 1.0 } else { 0.0 };
+    }
+    let lower_tail = poisson_cdf(k, lambda);
+    let upper_tail = if k == 0 { 1.0 } else { 1.0 - poisson_cdf(k - 1, lambda) };
+    (2.0 * lower_tail.min(upper_tail)).min(1.0) as Float
+}
+
+/// Join observed variants from `vcf_path` against the expected mutation counts already
+/// produced by the `expect` action, yielding an enrichment/depletion signal per transcript.
+pub fn enrichment_analysis<G: ReferenceSource + ?Sized>(
+    vcf_path: &str,
+    annotations: &[SeqAnnotation],
+    ref_genome: &G,
+    expected_mutations: &HashMap<String, ExpectedMutationCounts>,
+    filter_for_id: Option<&str>,
+) -> Result<Vec<EnrichmentRow>> {
+    let observed = read_mutations_from_vcf(vcf_path, 0)?;
+    let classified = classify_mutations(&observed, annotations, ref_genome, filter_for_id)?;
+    let observed_counts = tally(&classified, filter_for_id);
+
+    let mut result = Vec::new();
+    let no_observations = ObservedMutationCounts::default();
+    for (region, expected) in expected_mutations {
+        if let Some(id) = filter_for_id {
+            if region != id {
+                continue;
+            }
+        }
+        let observed = observed_counts.get(region).unwrap_or(&no_observations);
+        for mutation_type in MutationType::iter() {
+            if mutation_type == MutationType::Unknown {
+                continue;
+            }
+            let expected_count = expected.get(mutation_type);
+            let observed_count = observed.get(mutation_type);
+            let ratio = if expected_count != 0.0 {
+                observed_count as Float / expected_count
+            } else {
+                Float::NAN
+            };
+            let p_value = poisson_two_sided_p_value(observed_count, expected_count);
+            result.push(EnrichmentRow {
+                region: region.clone(),
+                mutation_type: mutation_type.as_str(),
+                observed: observed_count,
+                expected: expected_count,
+                ratio,
+                p_value,
+            });
+        }
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnrichmentRow {
+    region: String,
+    mutation_type: &'static str,
+    observed: usize,
+    expected: Float,
+    ratio: Float,
+    p_value: Float,
+}
+
+pub fn write_to_file(out_path: &str, rows: &[EnrichmentRow]) -> Result<()> {
+    let writer = get_writer(out_path)
+        .with_context(|| format!("failed to open file {} for writing", out_path))?;
+    let mut csv_writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(writer);
+    for row in rows {
+        csv_writer.serialize(row)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poisson_two_sided_p_value_at_the_mean_is_not_significant() {
+        // observing exactly the expected count should never look significant
+        let p_value = poisson_two_sided_p_value(5, 5.0);
+        assert!(p_value > 0.5, "p_value = {}", p_value);
+    }
+
+    #[test]
+    fn test_poisson_two_sided_p_value_is_small_far_from_the_mean() {
+        let p_value = poisson_two_sided_p_value(20, 2.0);
+        assert!(p_value < 0.01, "p_value = {}", p_value);
+    }
+
+    #[test]
+    fn test_poisson_two_sided_p_value_with_zero_expectation() {
+        assert_eq!(poisson_two_sided_p_value(0, 0.0), 1.0);
+        assert_eq!(poisson_two_sided_p_value(1, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_tally_counts_classified_mutations_per_region() {
+        let mutations = vec![
+            Mutation {
+                region: Some("gene_a".to_string()),
+                chromosome: "chr1".to_string(),
+                position: 1,
+                mutation_type: MutationType::Missense,
+                change: crate::observed::Change::PointMutation('A', 'T'),
+            },
+            Mutation {
+                region: Some("gene_a".to_string()),
+                chromosome: "chr1".to_string(),
+                position: 2,
+                mutation_type: MutationType::Synonymous,
+                change: crate::observed::Change::PointMutation('C', 'G'),
+            },
+            Mutation {
+                region: None,
+                chromosome: "chr1".to_string(),
+                position: 3,
+                mutation_type: MutationType::Missense,
+                change: crate::observed::Change::PointMutation('A', 'C'),
+            },
+        ];
+        let tallied = tally(&mutations, None);
+        assert_eq!(tallied.len(), 1);
+        let counts = &tallied["gene_a"];
+        assert_eq!(counts.get(MutationType::Missense), 1);
+        assert_eq!(counts.get(MutationType::Synonymous), 1);
+    }
+
+    #[test]
+    fn test_tally_respects_filter_for_id() {
+        let mutations = vec![
+            Mutation {
+                region: Some("gene_a".to_string()),
+                chromosome: "chr1".to_string(),
+                position: 1,
+                mutation_type: MutationType::Missense,
+                change: crate::observed::Change::PointMutation('A', 'T'),
+            },
+            Mutation {
+                region: Some("gene_b".to_string()),
+                chromosome: "chr1".to_string(),
+                position: 2,
+                mutation_type: MutationType::Missense,
+                change: crate::observed::Change::PointMutation('A', 'T'),
+            },
+        ];
+        let tallied = tally(&mutations, Some("gene_b"));
+        assert_eq!(tallied.len(), 1);
+        assert!(tallied.contains_key("gene_b"));
+    }
+}
@@ -1,25 +1,31 @@
 use std::collections::hash_map::HashMap;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 
 use anyhow::{Context, Result};
 
 use mutexpect::{possible_mutations, MutationEvent, SeqAnnotation};
 use pattern_partition_prediction::PaPaPred;
-use twobit::TwoBitFile;
 
 use crate::error::ParseError;
 use crate::io::{get_reader, get_writer};
+use crate::reference::ReferenceGenome;
 use crate::{Float, MutationType};
 
+/// `.pmb` (and `.pmb.gz`) files use the binary encoding; everything else is the text format.
+fn is_binary_path(path: &str) -> bool {
+    path.ends_with(".pmb") || path.ends_with(".pmb.gz")
+}
+
 type PossibleMutations = HashMap<String, Vec<MutationEvent>>;
 
 pub fn enumerate_possible_mutations(
     annotations: &[SeqAnnotation],
-    ref_genome: &TwoBitFile,
+    ref_genome: &dyn ReferenceGenome,
     mutation_rates: &PaPaPred,
     scaling_factor: f32,
     drop_nan: bool,
     filter_for_id: Option<&str>,
+    coverage: Option<&HashMap<String, Vec<f32>>>,
 ) -> Result<PossibleMutations> {
     let mut result = HashMap::new();
     let radius = mutation_rates.radius();
@@ -31,14 +37,35 @@ pub fn enumerate_possible_mutations(
         }
         let start = annotation.range.start - radius;
         let stop = annotation.range.stop + radius + 1;
-        let seq = ref_genome.sequence(&annotation.chr, start, stop)?;
-        match possible_mutations(&seq, &annotation, mutation_rates, drop_nan) {
+        let seq = String::from_utf8(ref_genome.sequence(&annotation.chr, start, stop)?)
+            .with_context(|| format!("reference sequence for {} is not valid UTF-8", annotation.name))?;
+
+        // A k-mer window that lands on an IUPAC ambiguity code has no single concrete
+        // probability; run `possible_mutations` once per base it's compatible with and
+        // average the results, instead of treating it as one of A/C/G/T.
+        let concrete_sequences = enumerate_concrete_sequences(&seq);
+        let enumerated: Result<Vec<Vec<MutationEvent>>, _> = concrete_sequences
+            .iter()
+            .map(|variant_seq| possible_mutations(variant_seq, &annotation, mutation_rates, drop_nan))
+            .collect();
+        let variant_events = match enumerated {
+            Ok(variant_events) => variant_events,
+            Err(e) => {
+                eprintln!(
+                    "[WARNING] Skipping faulty annotation {}: {}",
+                    annotation.name, e
+                );
+                continue;
+            }
+        };
+        match average_mutation_events(variant_events) {
             Ok(mut mutations) => {
-                if scaling_factor != 1.0 {
-                    for mutation in &mut mutations {
-                        mutation.probability *= scaling_factor;
-                    }
-                }
+                // Every callable-fraction-scaled site still enters `expect`/`sample` through
+                // this same `possible_mutations` map, so the expected counts and the
+                // Monte-Carlo null distribution downstream inherit the coverage weighting
+                // for free, without either module needing to know about coverage at all.
+                let callable_fractions = coverage.and_then(|coverage| coverage.get(&annotation.name));
+                scale_by_cds_coverage(&mut mutations, annotation, scaling_factor, callable_fractions);
                 result.insert(annotation.name.clone(), mutations);
             }
             Err(e) => {
@@ -53,7 +80,170 @@ pub fn enumerate_possible_mutations(
     Ok(result)
 }
 
+/// Scale `mutations` by `scaling_factor` times the callable fraction of the CDS each event
+/// falls in, rather than one fraction blended across the whole transcript. `MutationEvent`
+/// carries no site position (see `average_mutation_events` below), so `possible_mutations`'s
+/// flat, coding-order event vector is apportioned across `annotation.coding_sequences` in
+/// proportion to each CDS's base length -- the finest granularity obtainable without a
+/// position field on `MutationEvent` itself. `callable_fractions` is expected to line up
+/// one-to-one with `annotation.coding_sequences` (as produced by
+/// `coverage::callable_fraction_by_region`); a missing or short-by-one-CDS map falls back to
+/// a fraction of 1.0 for the uncovered region rather than erroring.
+fn scale_by_cds_coverage(
+    mutations: &mut [MutationEvent],
+    annotation: &SeqAnnotation,
+    scaling_factor: f32,
+    callable_fractions: Option<&Vec<f32>>,
+) {
+    if mutations.is_empty() {
+        return;
+    }
+    let region_lengths: Vec<usize> = if annotation.coding_sequences.is_empty() {
+        vec![annotation.range.len()]
+    } else {
+        annotation.coding_sequences.iter().map(|cds| cds.range.len()).collect()
+    };
+    let total_length: usize = region_lengths.iter().sum();
+    if total_length == 0 {
+        return;
+    }
+
+    let mut event_start = 0usize;
+    let mut bases_allotted = 0usize;
+    for (region_no, region_length) in region_lengths.iter().enumerate() {
+        bases_allotted += region_length;
+        let event_end = mutations.len() * bases_allotted / total_length;
+        let callable_fraction = callable_fractions
+            .and_then(|fractions| fractions.get(region_no))
+            .copied()
+            .unwrap_or(1.0);
+        let site_scaling_factor = scaling_factor * callable_fraction;
+        if site_scaling_factor != 1.0 {
+            for mutation in &mut mutations[event_start..event_end] {
+                mutation.probability *= site_scaling_factor;
+            }
+        }
+        event_start = event_end;
+    }
+}
+
+/// The concrete bases an IUPAC code is compatible with, in the same order regardless of
+/// input case. Unambiguous bases (including soft-masked lowercase) map to themselves, so
+/// expanding a fully unambiguous sequence is a no-op.
+fn iupac_concrete_bases(code: char) -> Vec<char> {
+    match code.to_ascii_uppercase() {
+        'A' | 'C' | 'G' | 'T' => vec![code],
+        'R' => vec!['A', 'G'],
+        'Y' => vec!['C', 'T'],
+        'W' => vec!['A', 'T'],
+        'S' => vec!['C', 'G'],
+        'K' => vec!['G', 'T'],
+        'M' => vec!['A', 'C'],
+        'B' => vec!['C', 'G', 'T'],
+        'D' => vec!['A', 'G', 'T'],
+        'H' => vec!['A', 'C', 'T'],
+        'V' => vec!['A', 'C', 'G'],
+        'N' => vec!['A', 'C', 'G', 'T'],
+        _ => vec![code], // gaps and anything else pass through unchanged
+    }
+}
+
+/// Expand every ambiguity code in `seq` into its compatible concrete sequences. A sequence
+/// with no ambiguity codes (the common case) comes back as a single clone of itself, so
+/// callers pay no cost beyond the allocation when there's nothing to expand.
+fn enumerate_concrete_sequences(seq: &str) -> Vec<String> {
+    let ambiguous_positions: Vec<(usize, Vec<char>)> = seq
+        .chars()
+        .enumerate()
+        .filter_map(|(position, base)| {
+            let options = iupac_concrete_bases(base);
+            if options.len() > 1 {
+                Some((position, options))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    if ambiguous_positions.is_empty() {
+        return vec![seq.to_string()];
+    }
+
+    let mut variants = vec![seq.chars().collect::<Vec<char>>()];
+    for (position, options) in ambiguous_positions {
+        let mut expanded = Vec::with_capacity(variants.len() * options.len());
+        for variant in &variants {
+            for &option in &options {
+                let mut with_option = variant.clone();
+                with_option[position] = option;
+                expanded.push(with_option);
+            }
+        }
+        variants = expanded;
+    }
+
+    variants.into_iter().map(|chars| chars.into_iter().collect()).collect()
+}
+
+/// Average the per-site probabilities `possible_mutations` produced for each concrete
+/// sequence `enumerate_concrete_sequences` derived from one ambiguous reference window.
+/// `possible_mutations` is called with `drop_nan=true`, which exists precisely because some
+/// k-mer contexts have no PaPa table entry -- and that's more likely to differ between
+/// concrete substitutions of an ambiguity code than between two unrelated sites, so a variant
+/// can come back with a different event count than the others. `MutationEvent` carries no
+/// site position to realign on, so rather than zip positionally (which would silently
+/// desync every later pairing), require every variant to agree on the mutation-type sequence
+/// and fail loudly if they don't.
+fn average_mutation_events(variants: Vec<Vec<MutationEvent>>) -> Result<Vec<MutationEvent>> {
+    let variant_count = variants.len() as Float;
+    let mut variants = variants.into_iter();
+    let mut averaged = variants.next().expect("at least one concrete sequence");
+    for events in variants {
+        if events.len() != averaged.len() {
+            return Err(anyhow::anyhow!(
+                "ambiguous reference window produced {} possible-mutation events for one \
+                 concrete substitution but {} for another; cannot average them",
+                averaged.len(),
+                events.len()
+            ));
+        }
+        for (averaged_event, event) in averaged.iter_mut().zip(events.into_iter()) {
+            if averaged_event.mutation_type != event.mutation_type {
+                return Err(anyhow::anyhow!(
+                    "ambiguous reference window's concrete substitutions disagree on mutation \
+                     type at the same site ({} vs {}); cannot average them",
+                    averaged_event.mutation_type,
+                    event.mutation_type
+                ));
+            }
+            averaged_event.probability += event.probability;
+        }
+    }
+    if variant_count > 1.0 {
+        for averaged_event in &mut averaged {
+            averaged_event.probability /= variant_count;
+        }
+    }
+    Ok(averaged)
+}
+
 pub fn write_to_file(out_path: &str, possible_mutations: &PossibleMutations) -> Result<()> {
+    if is_binary_path(out_path) {
+        write_to_file_binary(out_path, possible_mutations)
+    } else {
+        write_to_file_text(out_path, possible_mutations)
+    }
+}
+
+pub fn read_from_file(in_path: &str) -> Result<PossibleMutations> {
+    if is_binary_path(in_path) {
+        read_from_file_binary(in_path)
+    } else {
+        read_from_file_text(in_path)
+    }
+}
+
+fn write_to_file_text(out_path: &str, possible_mutations: &PossibleMutations) -> Result<()> {
     let writer = get_writer(out_path)
         .with_context(|| format!("failed to open file {} for writing", out_path))?;
     let mut buf_writer = BufWriter::new(writer);
@@ -73,7 +263,7 @@ pub fn write_to_file(out_path: &str, possible_mutations: &PossibleMutations) ->
     Ok(())
 }
 
-pub fn read_from_file(in_path: &str) -> Result<PossibleMutations> {
+fn read_from_file_text(in_path: &str) -> Result<PossibleMutations> {
     let mut result: PossibleMutations = HashMap::new();
     let reader = get_reader(in_path)
         .with_context(|| format!("failed to open file {} for reading", in_path))?;
@@ -95,8 +285,22 @@ pub fn read_from_file(in_path: &str) -> Result<PossibleMutations> {
                 .into());
             }
             let tokens: Vec<&str> = line.split(':').collect();
-            let mut_type: MutationType = tokens[0].parse::<u8>()?.into();
-            let probability: Float = tokens[1].parse()?;
+            if tokens.len() != 2 {
+                return Err(ParseError::new(format!(
+                    "Expected <mutation_type>:<probability> on line {} in file {}, got {:?}",
+                    line_no + 1,
+                    in_path,
+                    line
+                ))
+                .into());
+            }
+            let mut_type: MutationType = tokens[0]
+                .parse::<u8>()
+                .map_err(|e| ParseError::new(format!("Bad mutation type on line {} in file {}: {}", line_no + 1, in_path, e)))?
+                .into();
+            let probability: Float = tokens[1]
+                .parse()
+                .map_err(|e| ParseError::new(format!("Bad probability on line {} in file {}: {}", line_no + 1, in_path, e)))?;
             if let Some(gene) = &current_gene {
                 result
                     .get_mut(gene)
@@ -113,6 +317,89 @@ pub fn read_from_file(in_path: &str) -> Result<PossibleMutations> {
     Ok(result)
 }
 
+// binary encoding: a length-prefixed layout (varint region count, then per region:
+// utf8 name length + bytes, varint event count, then u8 mutation type + f32 probability
+// per event), wrapped by the usual gzip logic in get_writer/get_reader.
+
+fn write_varint(writer: &mut impl Write, mut value: u64) -> Result<()> {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        writer.write_all(&[byte])?;
+        if value == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<u64> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        result |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(result)
+}
+
+fn write_to_file_binary(out_path: &str, possible_mutations: &PossibleMutations) -> Result<()> {
+    let writer = get_writer(out_path)
+        .with_context(|| format!("failed to open file {} for writing", out_path))?;
+    let mut buf_writer = BufWriter::new(writer);
+    write_varint(&mut buf_writer, possible_mutations.len() as u64)?;
+    for (name, mutations) in possible_mutations {
+        let name_bytes = name.as_bytes();
+        write_varint(&mut buf_writer, name_bytes.len() as u64)?;
+        buf_writer.write_all(name_bytes)?;
+        write_varint(&mut buf_writer, mutations.len() as u64)?;
+        for mutation in mutations {
+            buf_writer.write_all(&[mutation.mutation_type as u8])?;
+            buf_writer.write_all(&mutation.probability.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn read_from_file_binary(in_path: &str) -> Result<PossibleMutations> {
+    let reader = get_reader(in_path)
+        .with_context(|| format!("failed to open file {} for reading", in_path))?;
+    let mut buf_reader = BufReader::new(reader);
+    let mut result: PossibleMutations = HashMap::new();
+    let region_count = read_varint(&mut buf_reader)?;
+    for _ in 0..region_count {
+        let name_len = read_varint(&mut buf_reader)? as usize;
+        let mut name_bytes = vec![0u8; name_len];
+        buf_reader.read_exact(&mut name_bytes)?;
+        let name = String::from_utf8(name_bytes)
+            .map_err(|e| ParseError::new(format!("Region name is not valid UTF-8 in file {}: {}", in_path, e)))?;
+
+        let event_count = read_varint(&mut buf_reader)?;
+        let mut events = Vec::with_capacity(event_count as usize);
+        for _ in 0..event_count {
+            let mut mut_type_byte = [0u8; 1];
+            buf_reader.read_exact(&mut mut_type_byte)?;
+            let mut_type: MutationType = mut_type_byte[0].into();
+
+            let mut probability_bytes = [0u8; 4];
+            buf_reader.read_exact(&mut probability_bytes)?;
+            let probability = Float::from_le_bytes(probability_bytes);
+
+            events.push(MutationEvent::new(mut_type, probability));
+        }
+        result.insert(name, events);
+    }
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,4 +442,39 @@ mod tests {
         let pm2 = read_from_file(path).unwrap();
         assert_eq!(pm, pm2);
     }
+
+    #[test]
+    fn test_text_to_binary_round_trip() {
+        use mutexpect::MutationEvent;
+
+        fn mevent(mut_type: &str, probability: Float) -> MutationEvent {
+            MutationEvent::new(mut_type.try_into().unwrap(), probability)
+        }
+
+        let text_path = "/tmp/unit_test.possible_mutations.txt";
+        let binary_path = "/tmp/unit_test.possible_mutations.pmb";
+
+        let mut pm: PossibleMutations = HashMap::new();
+        pm.insert(
+            "foo".to_string(),
+            vec![
+                mevent("synonymous", 0.1),
+                mevent("missense", 0.2),
+                mevent("nonsense", 0.3),
+            ],
+        );
+        pm.insert("bar".to_string(), vec![]);
+
+        write_to_file(text_path, &pm).unwrap();
+        let from_text = read_from_file(text_path).unwrap();
+        assert_eq!(pm, from_text);
+
+        write_to_file(binary_path, &from_text).unwrap();
+        let from_binary = read_from_file(binary_path).unwrap();
+        assert_eq!(from_text, from_binary);
+
+        write_to_file(text_path, &from_binary).unwrap();
+        let round_tripped = read_from_file(text_path).unwrap();
+        assert_eq!(pm, round_tripped);
+    }
 }
@@ -5,10 +5,23 @@ use std::path::Path;
 
 use anyhow::Result;
 
-use flate2::read::GzDecoder;
+use flate2::read::{GzDecoder, MultiGzDecoder};
 use flate2::write::GzEncoder;
 use flate2::Compression;
 
+use crate::bgzf;
+
+/// Use a `.tbi` tabix index beside `path` to read only the records overlapping
+/// `[start, stop)` on `chr`, instead of scanning the whole coordinate-sorted file.
+pub fn get_region_reader(path: &str, chr: &str, start: usize, stop: usize) -> Result<Box<dyn Read>> {
+    bgzf::get_region_reader(path, chr, start, stop)
+}
+
+/// Whether `path` has a `.tbi` index beside it, i.e. whether `get_region_reader` can be used.
+pub fn has_tabix_index(path: &str) -> bool {
+    Path::new(&format!("{}.tbi", path)).exists()
+}
+
 fn is_terminal_io_file(path: &str) -> bool {
     path == "-" || path == "/dev/stdout" || path == "/dev/stdin"
 }
@@ -35,7 +48,15 @@ pub fn get_reader(path: &str) -> Result<Box<dyn Read>> {
     } else {
         let fd = File::open(&path)?;
         if path.ends_with(".gz") {
-            Ok(Box::new(GzDecoder::new(fd)))
+            // BGZF concatenates many independent gzip members, one per block; a plain
+            // GzDecoder only reads the first member and silently truncates the rest, so this
+            // non-indexed full scan needs the multi-member decoder whenever the file is BGZF
+            // (tabix-indexed reads never reach here -- see bgzf::get_region_reader).
+            if bgzf::is_bgzf(path)? {
+                Ok(Box::new(MultiGzDecoder::new(fd)))
+            } else {
+                Ok(Box::new(GzDecoder::new(fd)))
+            }
         } else {
             Ok(Box::new(fd))
         }
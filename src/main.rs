@@ -4,13 +4,17 @@ use clap::{App, Arg};
 use pattern_partition_prediction::{PaPaPred, PaPaPredIndel};
 use twobit::TwoBitFile;
 
+mod bgzf;
 mod compare;
 mod counts;
+mod coverage;
+mod enrichment;
 mod enumerate;
 mod error;
 mod expect;
 mod io;
 mod observed;
+mod reference;
 mod sample;
 mod transform;
 
@@ -23,6 +27,7 @@ use crate::error::MissingCommandLineArgumentError;
 use crate::expect::expected_number_of_mutations;
 use crate::observed::classify_mutations;
 use crate::observed::read_mutations_from_file as read_observed_mutations_from_file;
+use crate::reference::{FastaReference, IndexedFastaReference, ReferenceGenome, ReferenceSource};
 use crate::sample::sample_mutations;
 
 fn require_initialization<'a, T>(
@@ -41,7 +46,7 @@ fn main() -> Result<()> {
         .author("Jörn Bethune")
         .about("Determine genes enriched with de-novo mutations")
         .after_help("If no --action is given, all actions are executed.\n\
-                     Possible actions are: transform, enumerate, expect, sample, classify, compare" )
+                     Possible actions are: transform, enumerate, expect, sample, classify, compare, analytic, fdr, enrich" )
         .arg(Arg::with_name("action")
              .long("action")
              .value_name("ACTION")
@@ -52,12 +57,12 @@ fn main() -> Result<()> {
         .arg(Arg::with_name("gff3")
              .long("gff3")
              .value_name("FILE")
-             .help("gff3 gene annotations file")
+             .help("gene annotations file, in GFF3 or GTF format (.gtf[.gz] is read as GTF)")
              .takes_value(true))
         .arg(Arg::with_name("genome")
              .long("genome")
              .value_name("FILE")
-             .help("A 2bit reference genome sequence file")
+             .help("A reference genome sequence file: 2bit, or plain (non-gzipped) .fa with a sibling .fai")
              .takes_value(true))
         .arg(Arg::with_name("point-mutation-probabilities")
              .long("point-mutation-probabilities")
@@ -74,6 +79,22 @@ fn main() -> Result<()> {
              .value_name("FILE")
              .help("A vcf-like file containing observed point mutations")
              .takes_value(true))
+        .arg(Arg::with_name("observed-vcf")
+             .long("observed-vcf")
+             .value_name("FILE")
+             .help("A VCF/BCF file containing observed variants, for --action enrich")
+             .takes_value(true))
+        .arg(Arg::with_name("coverage-bam")
+             .long("coverage-bam")
+             .value_name("FILE")
+             .help("An indexed BAM/CRAM file; scales each gene's expected mutation probabilities CDS-by-CDS by the fraction of that CDS covered at --min-coverage-depth, so a poorly sequenced exon doesn't drag down a well-covered one in the same gene")
+             .takes_value(true))
+        .arg(Arg::with_name("min-coverage-depth")
+             .long("min-coverage-depth")
+             .value_name("DEPTH")
+             .help("Minimum read depth for a position to count as callable when --coverage-bam is given")
+             .default_value("10")
+             .takes_value(true))
 
         // input/output file arguments
         .arg(Arg::with_name("genomic-regions")
@@ -91,6 +112,11 @@ fn main() -> Result<()> {
              .value_name("FILE")
              .help("Observed, classified point mutations")
              .takes_value(true))
+        .arg(Arg::with_name("classified-mutations-vcf")
+             .long("classified-mutations-vcf")
+             .value_name("FILE")
+             .help("Observed, classified mutations as a VCF with mutation_type/region INFO fields, for downstream VCF tooling (bcftools, IGV)")
+             .takes_value(true))
         .arg(Arg::with_name("expected-mutations")
              .long("expected-mutations")
              .value_name("FILE")
@@ -107,6 +133,18 @@ fn main() -> Result<()> {
              .help("Statistical test results for every gene")
              .default_value("-")
              .takes_value(true))
+        .arg(Arg::with_name("enrichment-results")
+             .long("enrichment-results")
+             .value_name("FILE")
+             .help("Observed-vs-expected mutation enrichment per gene and mutation type")
+             .default_value("-")
+             .takes_value(true))
+        .arg(Arg::with_name("fdr-inputs")
+             .long("fdr-inputs")
+             .value_name("FILE")
+             .help("One or more --significant-mutations files from separate --id-restricted runs, for --action fdr")
+             .takes_value(true)
+             .multiple(true))
 
         // non-file args
         .arg(Arg::with_name("id")
@@ -144,9 +182,32 @@ fn main() -> Result<()> {
      * Therefore the variables are all Option's.
      */
 
-    let ref_genome = {
+    // `enumerate` works off any ReferenceGenome implementation (Vec<u8>-returning). `.fa.gz`
+    // is routed here too so FastaReference::open can reject it with a clear error, rather
+    // than falling through to a confusing TwoBitFile-parsing failure.
+    let reference_genome: Option<Box<dyn ReferenceGenome>> = {
         if let Some(ref_genome_file) = matches.value_of("genome") {
-            Some(TwoBitFile::open(ref_genome_file, false)?)
+            if ref_genome_file.ends_with(".fa") || ref_genome_file.ends_with(".fa.gz") {
+                Some(Box::new(FastaReference::open(ref_genome_file)?))
+            } else {
+                Some(Box::new(TwoBitFile::open(ref_genome_file, false)?))
+            }
+        } else {
+            None
+        }
+    };
+
+    // `classify`/`enrich` work off any ReferenceSource implementation (String-returning),
+    // so a plain `.fa` + `.fai` can be used without first building a 2bit file. `.fa.gz` is
+    // still routed here rather than to TwoBitFile::open so the user gets IndexedFastaReference's
+    // clear "not supported" error instead of a confusing 2bit-parsing failure.
+    let reference_source: Option<Box<dyn ReferenceSource>> = {
+        if let Some(ref_genome_file) = matches.value_of("genome") {
+            if ref_genome_file.ends_with(".fa") || ref_genome_file.ends_with(".fa.gz") {
+                Some(Box::new(IndexedFastaReference::open(ref_genome_file)?))
+            } else {
+                Some(Box::new(TwoBitFile::open(ref_genome_file, false)?))
+            }
         } else {
             None
         }
@@ -175,23 +236,31 @@ fn main() -> Result<()> {
         }
     };
 
-    let observed_mutations = {
-        if let Some(observed_mutations_file) = matches.value_of("observed-mutations") {
-            Some(read_observed_mutations_from_file(
-                observed_mutations_file,
-                -1,
-            )?) //TODO expose adjustment parameter to CLI
-        } else {
-            None
-        }
-    };
-
     // action=transform
     let regions = {
         if run_all || matches.value_of("action") == Some("transform") {
             if let Some(gff3) = matches.value_of("gff3") {
-                let regions = transform::transform_gff3_annotations(gff3, id)?;
-                if let Some(regions_file) = matches.value_of("genomic-regions") {
+                let regions_file = matches.value_of("genomic-regions");
+                // When --id is restricted to one gene and a --genomic-regions file from an
+                // earlier full transform run already exists, resolve that gene's coordinate
+                // window from it and use the tabix fast path instead of a full file scan.
+                let known_window = id.zip(regions_file).and_then(|(gene_id, regions_path)| {
+                    mutexpect::read_sequence_annotations_from_file(regions_path, Some(gene_id))
+                        .ok()
+                        .and_then(|annotations| annotations.into_iter().next())
+                });
+                let regions = if let Some(annotation) = known_window {
+                    transform::transform_annotations_in_region(
+                        gff3,
+                        &annotation.chr,
+                        annotation.range.start,
+                        annotation.range.stop,
+                        id,
+                    )?
+                } else {
+                    transform::transform_annotations(gff3, id)?
+                };
+                if let Some(regions_file) = regions_file {
                     transform::write_sequence_annotations_to_file(regions_file, &regions)?;
                 }
                 if !run_all {
@@ -212,17 +281,64 @@ fn main() -> Result<()> {
         }
     };
 
+    let observed_mutations = {
+        if let Some(observed_vcf_file) = matches.value_of("observed-vcf") {
+            // When restricted to a single gene, fetch only its genomic span via the VCF's
+            // tabix/CSI index instead of scanning the whole callset.
+            let single_gene_region = id.and_then(|gene_id| {
+                regions
+                    .as_ref()
+                    .and_then(|regions| regions.iter().find(|region| region.name == gene_id))
+            });
+            if let Some(annotation) = single_gene_region {
+                Some(observed::read_mutations_from_vcf_region(
+                    observed_vcf_file,
+                    0,
+                    &annotation.chr,
+                    annotation.range.start,
+                    annotation.range.stop,
+                )?)
+            } else {
+                Some(observed::read_mutations_from_vcf(observed_vcf_file, 0)?)
+            }
+        } else if let Some(observed_mutations_file) = matches.value_of("observed-mutations") {
+            Some(read_observed_mutations_from_file(
+                observed_mutations_file,
+                -1,
+            )?) //TODO expose adjustment parameter to CLI
+        } else {
+            None
+        }
+    };
+
+    let coverage_by_region = {
+        if let Some(coverage_bam_file) = matches.value_of("coverage-bam") {
+            let min_depth: u32 = matches
+                .value_of("min-coverage-depth")
+                .expect("default value")
+                .parse()?;
+            Some(coverage::callable_fraction_by_region(
+                coverage_bam_file,
+                require_initialization(&regions, "--genomic-regions")?,
+                min_depth,
+            )?)
+        } else {
+            None
+        }
+    };
+
     //action=enumerate
     let possible_mutations = {
         if run_all || matches.value_of("action") == Some("enumerate") {
             let possible_mutations = enumerate_possible_mutations(
                 require_initialization(&regions, "--genomic-regions")?,
-                require_initialization(&ref_genome, "--genome")?,
+                require_initialization(&reference_genome, "--genome")?.as_ref(),
                 require_initialization(&papa, "--point-mutation-probabilities")?,
                 &papa_indel,
                 scaling_factor,
                 true,
                 id,
+                coverage_by_region.as_ref(),
             )?;
 
             if let Some(possible_mutations_file) = matches.value_of("possible-mutations") {
@@ -290,14 +406,17 @@ fn main() -> Result<()> {
         }
     };
 
-    std::mem::drop(possible_mutations); // let's free up some memory
+    let mut possible_mutations = possible_mutations;
+    if matches.value_of("action") != Some("analytic") {
+        possible_mutations = None; // let's free up some memory; --action analytic still needs it below
+    }
 
     let classified_mutations = {
         if run_all || matches.value_of("action") == Some("classify") {
             let classified_mutations = classify_mutations(
                 require_initialization(&observed_mutations, "--observed-mutations")?,
                 require_initialization(&regions, "--genomic-regions")?,
-                require_initialization(&ref_genome, "--genome")?,
+                require_initialization(&reference_source, "--genome")?.as_ref(),
                 id,
             )?;
 
@@ -314,6 +433,9 @@ fn main() -> Result<()> {
                     observed::write_to_file(classified_mutations_file, &classified_mutations)?;
                 }
             }
+            if let Some(classified_mutations_vcf_file) = matches.value_of("classified-mutations-vcf") {
+                observed::write_to_vcf(classified_mutations_vcf_file, &classified_mutations)?;
+            }
             if !run_all {
                 // we are done here
                 return Ok(());
@@ -349,6 +471,52 @@ fn main() -> Result<()> {
         }
     };
 
+    // action=analytic (opt-in only, not part of the default pipeline): exact Poisson-binomial
+    // p-values from `possible_mutations` instead of Monte-Carlo samples, so it needs no
+    // --sampled-mutations file at all.
+    if matches.value_of("action") == Some("analytic") {
+        let significant_mutations = compare::compare_mutations_analytic(
+            require_initialization(&classified_mutations, "--classified-mutations")?,
+            require_initialization(&expected_mutations, "--expected-mutations")?,
+            require_initialization(&possible_mutations, "--possible-mutations")?,
+            id,
+        )?;
+        if let Some(significant_mutations_file) = matches.value_of("significant-mutations") {
+            compare::write_to_file(significant_mutations_file, &significant_mutations)?;
+        }
+        return Ok(());
+    }
+
+    // action=fdr (opt-in only, not part of the default pipeline): reconcile the
+    // --significant-mutations files from one or more --id-restricted runs into a single
+    // table with Benjamini-Hochberg q-values and Bonferroni-adjusted p-values computed
+    // over the pooled set, since each restricted run only ever saw one gene's p-value.
+    if matches.value_of("action") == Some("fdr") {
+        let input_files: Option<Vec<&str>> =
+            matches.values_of("fdr-inputs").map(|values| values.collect());
+        let input_files = require_initialization(&input_files, "--fdr-inputs")?;
+        let reconciled = compare::reconcile_significant_mutations(input_files)?;
+        if let Some(significant_mutations_file) = matches.value_of("significant-mutations") {
+            compare::write_to_file(significant_mutations_file, &reconciled)?;
+        }
+        return Ok(());
+    }
+
+    // action=enrich (opt-in only, not part of the default pipeline)
+    if matches.value_of("action") == Some("enrich") {
+        let enrichment_results = enrichment::enrichment_analysis(
+            require_initialization(&matches.value_of("observed-vcf"), "--observed-vcf")?,
+            require_initialization(&regions, "--genomic-regions")?,
+            require_initialization(&reference_source, "--genome")?.as_ref(),
+            require_initialization(&expected_mutations, "--expected-mutations")?,
+            id,
+        )?;
+        if let Some(enrichment_results_file) = matches.value_of("enrichment-results") {
+            enrichment::write_to_file(enrichment_results_file, &enrichment_results)?;
+        }
+        return Ok(());
+    }
+
     if !run_all {
         return Err(anyhow::anyhow!(
             "Invalid --action parameter: {}",
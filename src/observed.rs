@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::{BufWriter, Write};
 use std::path::Path;
 
@@ -6,14 +7,15 @@ use serde::{Deserialize, Serialize, Serializer, Deserializer};
 use serde::de::{Visitor, Unexpected};
 
 use mutexpect::interval::Interval;
-use mutexpect::{MutationType, PointMutationClassifier};
+use mutexpect::{MutationType, PointMutationClassifier, Strand};
+use rust_htslib::bcf::{self, Format, Header, Read as BcfRead, Writer};
 use tabfile::Tabfile;
-use twobit::TwoBitFile;
 
 use crate::compare::tally_up_observed_mutations;
 use crate::counts::ObservedMutationCounts;
 use crate::error::ParseError;
 use crate::io::{get_reader, get_writer};
+use crate::reference::ReferenceSource;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Mutation {
@@ -60,6 +62,61 @@ impl Mutation {
             None
         }
     }
+
+    /// Left-align an indel into its canonical representation, so that unnormalized indels
+    /// from different callers (e.g. `AGG->A` vs `GGA->G` describing the same event)
+    /// classify consistently. A no-op for point mutations.
+    pub fn normalize<G: ReferenceSource + ?Sized>(&mut self, genome: &G, chr: &str) -> Result<()> {
+        let (mut from, mut to) = match &self.change {
+            Change::Indel(from, to) => (from.clone().into_bytes(), to.clone().into_bytes()),
+            Change::PointMutation(_, _) => return Ok(()),
+        };
+
+        // (1) trim identical trailing bases while both alleles are longer than one base
+        while from.len() > 1 && to.len() > 1 && from.last() == to.last() {
+            from.pop();
+            to.pop();
+        }
+
+        // (2) trim identical leading bases while both alleles are longer than one base,
+        // adjusting position forward accordingly
+        while from.len() > 1 && to.len() > 1 && from[0] == to[0] {
+            from.remove(0);
+            to.remove(0);
+            self.position += 1;
+        }
+
+        // (3) left-shift: keep rotating the indel one base further left for as long as the
+        // anchor base (the reference base at `position`, immediately left of the inserted or
+        // deleted bases) equals the last base of the longer allele; each step prepends the
+        // reference base left of the anchor to both alleles and drops their last base.
+        // This has to be driven off whether a shift is actually possible, not off anchor-base
+        // equality: by VCF convention the shorter allele left by (1)/(2) is always the single
+        // anchor base shared with the longer allele's first base, so `from[0] != to[0]` is
+        // never true here and would make this loop dead code.
+        while self.position > 0 {
+            let longer_last = if from.len() >= to.len() {
+                *from.last().expect("non-empty")
+            } else {
+                *to.last().expect("non-empty")
+            };
+            if from[0] != longer_last {
+                break;
+            }
+            let left_base = genome.sequence(chr, self.position - 1, self.position)?.into_bytes()[0];
+            from.insert(0, left_base);
+            to.insert(0, left_base);
+            from.pop();
+            to.pop();
+            self.position -= 1;
+        }
+
+        self.change = Change::Indel(
+            String::from_utf8(from).expect("ACGT alphabet"),
+            String::from_utf8(to).expect("ACGT alphabet"),
+        );
+        Ok(())
+    }
 }
 
 impl Change {
@@ -134,16 +191,18 @@ impl<'de> Visitor<'de> for ChangeVisitor {
 }
 
 
-pub fn classify_mutations(
+pub fn classify_mutations<G: ReferenceSource + ?Sized>(
     observed_mutations: &[Mutation],
     annotations: &[mutexpect::SeqAnnotation],
-    genome: &TwoBitFile,
+    genome: &G,
     filter_for_id: Option<&str>,
 ) -> Result<Vec<Mutation>> {
     let mut result = Vec::new();
 
     let flank = 2; // number of flanking bases left and right needed to classify all coding point mutations
 
+    let mutations_by_chromosome = index_mutations_by_chromosome(observed_mutations);
+
     for annotation in annotations {
         if let Some(id) = filter_for_id {
             if id != annotation.name {
@@ -163,14 +222,23 @@ pub fn classify_mutations(
 
         let classifier = PointMutationClassifier::new(&annotation, 2);
         let mut relevant_mutations =
-            filter_observed_mutations(&observed_mutations, &annotation.chr, annotation.range);
+            filter_observed_mutations(&mutations_by_chromosome, &annotation.chr, annotation.range);
         for mutation in &mut relevant_mutations {
             let sequence_context: Vec<char> = {
                 assert!(annotation.range.start <= mutation.position);
                 let middle = mutation.position - annotation.range.start + flank;
                 seq_of_region[middle - flank..middle + flank + 1].into()
             };
-            assert_eq!(sequence_context[2], mutation.ref_base()); // sanity-check right reference genome
+            // exact equality would reject a legitimate ambiguity code (R/Y/W/...) in the
+            // 2bit reference, so compare base-sets instead of characters
+            assert!(
+                bases_compatible(sequence_context[2], mutation.ref_base()),
+                "reference base {} at {}:{} is incompatible with observed ref allele {}",
+                sequence_context[2],
+                annotation.chr,
+                mutation.position,
+                mutation.ref_base()
+            );
 
             let overlapping_intron = annotation.find_intron(mutation.position);
 
@@ -178,26 +246,47 @@ pub fn classify_mutations(
             classified_mutation.region = Some(annotation.name.clone());
             match mutation.change {
                 Change::PointMutation(_, _) => {
-                    let mut mutation_type = classifier.classify_by_position(
-                        mutation.position,
-                        &sequence_context,
-                        &overlapping_intron, // may be none
-                    );
-
-                    if mutation_type == MutationType::Unknown {
-                        if let Some(overlapping_cds) = annotation.find_cds(mutation.position) {
-                            mutation_type = classifier.classify_coding_mutation(
-                                mutation.position,
-                                &sequence_context,
-                                mutation.alt_base().expect("point mutation"),
-                                &overlapping_cds,
-                            );
+                    let alt_allele = mutation.alt_base().expect("point mutation");
+                    if bases_compatible(mutation.ref_base(), alt_allele) {
+                        // ref and alt base-sets overlap (e.g. ref=R{A,G}, alt=A): the two
+                        // alleles aren't distinguishable from the reference's own ambiguity,
+                        // so there's no genuine mutation to classify here
+                        classified_mutation.mutation_type = MutationType::Unknown;
+                    } else {
+                        let mut mutation_type = classifier.classify_by_position(
+                            mutation.position,
+                            &sequence_context,
+                            &overlapping_intron, // may be none
+                        );
+
+                        if mutation_type == MutationType::Unknown {
+                            if let Some(overlapping_cds) = annotation.find_cds(mutation.position) {
+                                // Codons read in transcription order, so on the minus strand the
+                                // reference window and the alt allele both need to be
+                                // reverse-complemented before classification; the emitted
+                                // `Mutation` still carries the original genomic alleles.
+                                let (coding_context, coding_alt_base) = if annotation.strand == Strand::Minus {
+                                    (
+                                        reverse_complement(&sequence_context),
+                                        complement_base(alt_allele),
+                                    )
+                                } else {
+                                    (sequence_context.clone(), alt_allele)
+                                };
+                                mutation_type = classifier.classify_coding_mutation(
+                                    mutation.position,
+                                    &coding_context,
+                                    coding_alt_base,
+                                    &overlapping_cds,
+                                );
+                            }
                         }
+                        classified_mutation.mutation_type = mutation_type;
                     }
-                    classified_mutation.mutation_type = mutation_type;
                 },
                 Change::Indel(_, _) => {
-                    let mutation_type = if let Some(_overlapping_cds) = annotation.find_cds(mutation.position + 1 ) { // +1 to ignore anchor base
+                    classified_mutation.normalize(genome, &annotation.chr)?;
+                    let mutation_type = if let Some(_overlapping_cds) = annotation.find_cds(classified_mutation.position + 1 ) { // +1 to ignore anchor base
                         if classified_mutation.change.is_frameshift() {
                             MutationType::FrameshiftIndel
                         } else {
@@ -254,19 +343,226 @@ pub fn read_mutations_from_file<P: AsRef<Path>>(
     Ok(result)
 }
 
-fn filter_observed_mutations<'a>(
-    mutations: &'a [Mutation],
+/// Read observed mutations from a VCF/BCF file (including bgzipped/indexed input) via
+/// `rust_htslib`, producing the same `Vec<Mutation>` as `read_mutations_from_file`.
+/// Multi-allelic and multi-sample records are supported, since only the site-level
+/// `REF`/`ALT` alleles are used; sample genotypes are ignored. Symbolic ALTs (`<DEL>`,
+/// `<INS>`, breakends containing `[`/`]`) are skipped with a warning, since `Mutation::new`
+/// only understands literal ref/alt sequences. Non-`PASS` records are silently excluded from
+/// the returned mutations, but not from the user's attention: a single summary warning reports
+/// how many were dropped.
+pub fn read_mutations_from_vcf(vcf_path: &str, adjust: i64) -> Result<Vec<Mutation>> {
+    let mut reader = bcf::Reader::from_path(vcf_path)
+        .with_context(|| format!("failed to open VCF/BCF file {}", vcf_path))?;
+    let header = reader.header().clone();
+    let mut result = Vec::new();
+    let mut filtered_out = 0usize;
+
+    for record_result in reader.records() {
+        let record = record_result?;
+        if !record_passes_filter(&record, &header) {
+            filtered_out += 1;
+            continue;
+        }
+        result.extend(mutations_from_record(&record, &header, adjust)?);
+    }
+    if filtered_out > 0 {
+        eprintln!(
+            "[WARNING] Skipped {} non-PASS record(s) in {}",
+            filtered_out, vcf_path
+        );
+    }
+
+    Ok(result)
+}
+
+/// Like `read_mutations_from_vcf`, but restricted to the genomic window `[start, stop)` on
+/// `chr`, using the file's tabix/CSI index to `fetch` only the overlapping records. This is
+/// what lets `--id`-restricted single-gene runs avoid scanning an entire cohort callset.
+/// Falls back to a full scan (and a warning) if `vcf_path` has no index.
+pub fn read_mutations_from_vcf_region(
+    vcf_path: &str,
+    adjust: i64,
     chr: &str,
-    genomic_region: Interval,
-) -> Vec<&'a Mutation> {
+    start: usize,
+    stop: usize,
+) -> Result<Vec<Mutation>> {
+    match bcf::IndexedReader::from_path(vcf_path) {
+        Ok(mut reader) => {
+            let header = reader.header().clone();
+            let rid = header
+                .name2rid(chr.as_bytes())
+                .with_context(|| format!("contig {} not found in {}", chr, vcf_path))?;
+            reader
+                .fetch(rid, start as u64, Some(stop as u64))
+                .with_context(|| format!("failed to fetch {}:{}-{} from {}", chr, start, stop, vcf_path))?;
+
+            let mut result = Vec::new();
+            let mut filtered_out = 0usize;
+            for record_result in reader.records() {
+                let record = record_result?;
+                if !record_passes_filter(&record, &header) {
+                    filtered_out += 1;
+                    continue;
+                }
+                result.extend(mutations_from_record(&record, &header, adjust)?);
+            }
+            if filtered_out > 0 {
+                eprintln!(
+                    "[WARNING] Skipped {} non-PASS record(s) in {}:{}-{}",
+                    filtered_out, vcf_path, chr, start
+                );
+            }
+            Ok(result)
+        }
+        Err(_) => {
+            eprintln!(
+                "[WARNING] No tabix/CSI index found for {}; scanning the whole file for region {}:{}-{}",
+                vcf_path, chr, start, stop
+            );
+            let mutations = read_mutations_from_vcf(vcf_path, adjust)?;
+            Ok(mutations
+                .into_iter()
+                .filter(|mutation| {
+                    mutation.chromosome == chr
+                        && mutation.position >= start
+                        && mutation.position < stop
+                })
+                .collect())
+        }
+    }
+}
+
+/// Expand one VCF/BCF record into a `Mutation` per non-symbolic ALT allele. Shared by the
+/// whole-file and indexed-region readers so they stay in lockstep. Callers are expected to
+/// have already skipped non-`PASS` records via `record_passes_filter`, so that they can also
+/// tally and report how many were dropped.
+fn mutations_from_record(
+    record: &bcf::Record,
+    header: &bcf::header::HeaderView,
+    adjust: i64,
+) -> Result<Vec<Mutation>> {
+    let rid = record.rid().context("record without a contig")?;
+    let chromosome = String::from_utf8_lossy(header.rid2name(rid)?).to_string();
+    let position = {
+        let value = record.pos();
+        (value + adjust) as usize
+    };
+
     let mut result = Vec::new();
-    for mutation in mutations {
-        // I assume no particular ordering. Otherwise a binary search might be faster
-        if mutation.chromosome == chr && genomic_region.contains(mutation.position) {
-            result.push(mutation)
+    let alleles = record.alleles();
+    let reference = String::from_utf8_lossy(alleles[0]).to_string();
+    for alt_allele in &alleles[1..] {
+        let alt = String::from_utf8_lossy(alt_allele).to_string();
+        if is_symbolic_allele(&alt) {
+            eprintln!(
+                "[WARNING] Skipping symbolic ALT allele {} at {}:{}",
+                alt, chromosome, position
+            );
+            continue;
         }
+        result.push(Mutation::new(
+            None,
+            chromosome.clone(),
+            position,
+            reference.clone(),
+            alt,
+        ));
+    }
+    Ok(result)
+}
+
+/// Whether a record is unfiltered (`.`) or has explicitly passed (`PASS`). Records flagged
+/// with any other FILTER value are excluded from the observed set.
+fn record_passes_filter(record: &bcf::Record, header: &bcf::header::HeaderView) -> bool {
+    match header.name_to_id(b"PASS") {
+        Ok(pass_id) => record.has_filter(&pass_id) || record.filters().next().is_none(),
+        Err(_) => true, // header doesn't even declare PASS; don't second-guess it
+    }
+}
+
+fn is_symbolic_allele(allele: &str) -> bool {
+    allele.starts_with('<') || allele.contains('[') || allele.contains(']')
+}
+
+/// Bitmask over {A,C,G,T} (bit 0=A, 1=C, 2=G, 3=T) for one IUPAC nucleotide code, so that
+/// ambiguity codes (R/Y/W/S/K/M/B/D/H/V/N) can be compared to a concrete allele, or to each
+/// other, without requiring an exact character match.
+fn iupac_bases(code: char) -> u8 {
+    match code.to_ascii_uppercase() {
+        'A' => 0b0001,
+        'C' => 0b0010,
+        'G' => 0b0100,
+        'T' => 0b1000,
+        'R' => 0b0101, // A/G
+        'Y' => 0b1010, // C/T
+        'W' => 0b1001, // A/T
+        'S' => 0b0110, // C/G
+        'K' => 0b1100, // G/T
+        'M' => 0b0011, // A/C
+        'B' => 0b1110, // C/G/T
+        'D' => 0b1101, // A/G/T
+        'H' => 0b1011, // A/C/T
+        'V' => 0b0111, // A/C/G
+        'N' => 0b1111, // A/C/G/T
+        _ => 0b0000,
+    }
+}
+
+/// Whether two IUPAC codes could describe the same physical base, i.e. their base-sets
+/// overlap. Used in place of exact equality wherever a reference base might carry an
+/// ambiguity code.
+fn bases_compatible(a: char, b: char) -> bool {
+    iupac_bases(a) & iupac_bases(b) != 0
+}
+
+fn complement_base(base: char) -> char {
+    match base {
+        'A' => 'T',
+        'C' => 'G',
+        'G' => 'C',
+        'T' => 'A',
+        'a' => 't',
+        'c' => 'g',
+        'g' => 'c',
+        't' => 'a',
+        other => other,
     }
-    result
+}
+
+fn reverse_complement(bases: &[char]) -> Vec<char> {
+    bases.iter().rev().map(|&base| complement_base(base)).collect()
+}
+
+/// Group mutations by chromosome and sort each group by position, so that
+/// `filter_observed_mutations` can binary-search a region instead of scanning every
+/// mutation for every annotation.
+fn index_mutations_by_chromosome(mutations: &[Mutation]) -> HashMap<&str, Vec<&Mutation>> {
+    let mut index: HashMap<&str, Vec<&Mutation>> = HashMap::new();
+    for mutation in mutations {
+        index
+            .entry(mutation.chromosome.as_str())
+            .or_insert_with(Vec::new)
+            .push(mutation);
+    }
+    for chromosome_mutations in index.values_mut() {
+        chromosome_mutations.sort_unstable_by_key(|mutation| mutation.position);
+    }
+    index
+}
+
+fn filter_observed_mutations<'a>(
+    mutations_by_chromosome: &HashMap<&str, Vec<&'a Mutation>>,
+    chr: &str,
+    genomic_region: Interval,
+) -> Vec<&'a Mutation> {
+    let mutations = match mutations_by_chromosome.get(chr) {
+        Some(mutations) => mutations,
+        None => return Vec::new(), // no observed mutations on this chromosome at all
+    };
+    let start = mutations.partition_point(|mutation| mutation.position < genomic_region.start);
+    let stop = mutations.partition_point(|mutation| mutation.position < genomic_region.stop);
+    mutations[start..stop].to_vec()
 }
 
 // serialization stuff //
@@ -315,6 +611,54 @@ pub fn sum_up_and_write_to_file(
     Ok(())
 }
 
+/// Write classified mutations as a VCF instead of the bespoke tab format from
+/// `write_to_file`, so downstream VCF tooling (bcftools, IGV) can consume them directly.
+/// `mutation_type` and `region` are recorded as INFO fields; `CHROM`/`POS`/`REF`/`ALT` are
+/// reconstructed from `Change` (point mutations directly, indels from the stored alleles).
+pub fn write_to_vcf(out_path: &str, annotated_mutations: &[Mutation]) -> Result<()> {
+    let mut header = Header::new();
+    header.push_record(
+        br#"##INFO=<ID=mutation_type,Number=1,Type=String,Description="Functional classification of the variant, e.g. Synonymous, Missense, Nonsense, FrameshiftIndel, InFrameIndel, Intronic">"#,
+    );
+    header.push_record(
+        br#"##INFO=<ID=region,Number=1,Type=String,Description="Transcript/annotation id the variant was classified against">"#,
+    );
+
+    let mut seen_chromosomes = std::collections::HashSet::new();
+    for mutation in annotated_mutations {
+        if seen_chromosomes.insert(mutation.chromosome.clone()) {
+            header.push_record(format!("##contig=<ID={}>", mutation.chromosome).as_bytes());
+        }
+    }
+
+    let mut writer = Writer::from_path(out_path, &header, true, Format::Vcf)
+        .with_context(|| format!("failed to open file {} for writing", out_path))?;
+
+    for mutation in annotated_mutations {
+        let mut record = writer.empty_record();
+        let rid = writer
+            .header()
+            .name2rid(mutation.chromosome.as_bytes())
+            .with_context(|| format!("unknown contig {} in VCF header", mutation.chromosome))?;
+        record.set_rid(Some(rid));
+        record.set_pos(mutation.position as i64);
+
+        let (reference, alt) = match &mutation.change {
+            Change::PointMutation(from, to) => (from.to_string(), to.to_string()),
+            Change::Indel(from, to) => (from.clone(), to.clone()),
+        };
+        record.set_alleles(&[reference.as_bytes(), alt.as_bytes()])?;
+
+        record.push_info_string(b"mutation_type", &[mutation.mutation_type.as_str().as_bytes()])?;
+        if let Some(region) = &mutation.region {
+            record.push_info_string(b"region", &[region.as_bytes()])?;
+        }
+
+        writer.write(&record)?;
+    }
+    Ok(())
+}
+
 pub fn read_from_file(in_path: &str) -> Result<Vec<Mutation>> {
     let mut result = Vec::new();
     let reader = get_reader(in_path)
@@ -333,6 +677,111 @@ pub fn read_from_file(in_path: &str) -> Result<Vec<Mutation>> {
 mod tests {
     use super::*;
 
+    /// An in-memory `ReferenceSource` over a single contig, for exercising `normalize`
+    /// without needing a real 2bit/FASTA file on disk.
+    struct FakeGenome {
+        chr: String,
+        sequence: Vec<u8>,
+    }
+
+    impl ReferenceSource for FakeGenome {
+        fn sequence(&self, chr: &str, start: usize, stop: usize) -> Result<String> {
+            assert_eq!(chr, self.chr);
+            Ok(String::from_utf8(self.sequence[start..stop].to_vec())?)
+        }
+    }
+
+    #[test]
+    fn test_normalize_trims_shared_trailing_base() {
+        // AGG->AG and AG->A describe the same one-base deletion; normalizing should
+        // trim the redundant shared suffix down to the minimal representation. The
+        // trimmed anchor (A) differs from the deleted base (G), so step (3)'s left-shift
+        // does not apply, isolating step (1)'s trimming behavior.
+        let genome = FakeGenome {
+            chr: "chr1".to_string(),
+            sequence: b"CCCCCCCCC".to_vec(),
+        };
+        let mut mutation =
+            Mutation::new(None, "chr1".to_string(), 5, "AGG".to_string(), "AG".to_string());
+        mutation.normalize(&genome, "chr1").unwrap();
+        assert_eq!(mutation.position, 5);
+        assert_eq!(mutation.change, Change::Indel("AG".to_string(), "A".to_string()));
+    }
+
+    #[test]
+    fn test_normalize_trims_shared_leading_base_and_shifts_position() {
+        // AAG->AA has a redundant shared prefix base; trimming it shifts the anchor
+        // one base to the right. The new anchor (A) differs from the deleted base (G),
+        // which isolates step (2)'s trimming from step (3)'s left-shift.
+        let genome = FakeGenome {
+            chr: "chr1".to_string(),
+            sequence: b"CCCCCCCCC".to_vec(),
+        };
+        let mut mutation =
+            Mutation::new(None, "chr1".to_string(), 5, "AAG".to_string(), "AA".to_string());
+        mutation.normalize(&genome, "chr1").unwrap();
+        assert_eq!(mutation.position, 6);
+        assert_eq!(mutation.change, Change::Indel("AG".to_string(), "A".to_string()));
+    }
+
+    #[test]
+    fn test_normalize_left_shifts_minimal_repeat_deletion() {
+        // AGA->A at 1 (deleting "GA") is already minimal, but deleting "AG" after the C at 0
+        // yields the same sequence, so it shifts left to the contig start.
+        let genome = FakeGenome {
+            chr: "chr1".to_string(),
+            sequence: b"CAGAGAGAT".to_vec(),
+        };
+        let mut mutation =
+            Mutation::new(None, "chr1".to_string(), 1, "AGA".to_string(), "A".to_string());
+        mutation.normalize(&genome, "chr1").unwrap();
+        assert_eq!(mutation.position, 0);
+        assert_eq!(mutation.change, Change::Indel("CAG".to_string(), "C".to_string()));
+    }
+
+    #[test]
+    fn test_normalize_converges_out_of_phase_homopolymer_deletions() {
+        // "CAGGGGT": a run of four Gs (indices 2..6). Deleting one G can be reported by
+        // different callers anchored at any G in the run; all of them describe the same
+        // physical deletion and must left-shift to the same canonical (position, from, to).
+        let genome = FakeGenome {
+            chr: "chr1".to_string(),
+            sequence: b"CAGGGGT".to_vec(),
+        };
+
+        let mut from_third_g =
+            Mutation::new(None, "chr1".to_string(), 3, "GG".to_string(), "G".to_string());
+        from_third_g.normalize(&genome, "chr1").unwrap();
+
+        let mut from_fourth_g =
+            Mutation::new(None, "chr1".to_string(), 4, "GG".to_string(), "G".to_string());
+        from_fourth_g.normalize(&genome, "chr1").unwrap();
+
+        assert_eq!(from_third_g.position, 1);
+        assert_eq!(from_third_g.change, Change::Indel("AG".to_string(), "A".to_string()));
+        assert_eq!(from_third_g, from_fourth_g);
+    }
+
+    #[test]
+    fn test_normalize_is_noop_for_point_mutation() {
+        let genome = FakeGenome {
+            chr: "chr1".to_string(),
+            sequence: b"CAGAGAGAT".to_vec(),
+        };
+        let mut mutation =
+            Mutation::new(None, "chr1".to_string(), 4, "A".to_string(), "T".to_string());
+        mutation.normalize(&genome, "chr1").unwrap();
+        assert_eq!(mutation.position, 4);
+        assert_eq!(mutation.change, Change::PointMutation('A', 'T'));
+    }
+
+    #[test]
+    fn test_reverse_complement_of_known_codon() {
+        // ATG (Met, read 5'->3' on the plus strand) on the minus strand is read as CAT.
+        let codon: Vec<char> = "ATG".chars().collect();
+        assert_eq!(reverse_complement(&codon), vec!['C', 'A', 'T']);
+    }
+
     #[test]
     fn test_observed_mutations_io() {
         let path = "/tmp/unit_test.observed_mutations";
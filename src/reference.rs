@@ -0,0 +1,209 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+use anyhow::{Context, Result};
+
+use bio::io::fasta::IndexedReader;
+use twobit::TwoBitFile;
+
+/// A source of reference genome sequence, so callers don't need to care whether the
+/// reference is backed by a 2bit file or an indexed FASTA file.
+pub trait ReferenceGenome {
+    fn sequence(&self, chr: &str, start: usize, stop: usize) -> Result<Vec<u8>>;
+}
+
+impl ReferenceGenome for TwoBitFile {
+    fn sequence(&self, chr: &str, start: usize, stop: usize) -> Result<Vec<u8>> {
+        Ok(self.sequence(chr, start, stop)?.into_bytes())
+    }
+}
+
+struct FaiEntry {
+    offset: u64,
+    line_bases: u64,
+    line_width: u64,
+}
+
+/// An indexed plain-text FASTA reference (`.fa` with a sibling `.fai`), parsed the way
+/// `rust-bio` does: the index gives, per contig, a byte offset plus the number of bases
+/// and bytes per line, which is enough to seek directly to any subsequence.
+///
+/// `.fa.gz` is rejected by `open`: the `.fai` byte offsets it records are offsets into the
+/// *uncompressed* sequence, and seeking directly into a gzip stream at those offsets would
+/// silently read garbage. Supporting it would need BGZF-block-aware seeking (plain gzip
+/// isn't seekable at arbitrary offsets either), which this reader doesn't implement.
+pub struct FastaReference {
+    path: String,
+    index: HashMap<String, FaiEntry>,
+}
+
+impl FastaReference {
+    pub fn open(path: &str) -> Result<Self> {
+        if path.ends_with(".gz") {
+            return Err(anyhow::anyhow!(
+                "{} is gzip-compressed, but FastaReference only supports plain, unindexed-byte-offset \
+                 .fa files (seeking into a gzip-compressed .fa.gz by .fai offset would read garbage); \
+                 decompress it first or build a 2bit file instead",
+                path
+            ));
+        }
+        let fai_path = format!("{}.fai", path);
+        let fai_file = File::open(&fai_path)
+            .with_context(|| format!("failed to open FASTA index {}", fai_path))?;
+        let mut index = HashMap::new();
+        for line in BufReader::new(fai_file).lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 5 {
+                return Err(anyhow::anyhow!(
+                    "malformed line in FASTA index {}: {}",
+                    fai_path,
+                    line
+                ));
+            }
+            index.insert(
+                fields[0].to_string(),
+                FaiEntry {
+                    offset: fields[2].parse()?,
+                    line_bases: fields[3].parse()?,
+                    line_width: fields[4].parse()?,
+                },
+            );
+        }
+        Ok(Self {
+            path: path.to_string(),
+            index,
+        })
+    }
+}
+
+impl ReferenceGenome for FastaReference {
+    fn sequence(&self, chr: &str, start: usize, stop: usize) -> Result<Vec<u8>> {
+        let entry = self
+            .index
+            .get(chr)
+            .with_context(|| format!("contig {} not found in FASTA index", chr))?;
+        let mut file =
+            File::open(&self.path).with_context(|| format!("failed to open FASTA file {}", self.path))?;
+        let mut result = Vec::with_capacity(stop - start);
+        let mut pos = start as u64;
+        let stop = stop as u64;
+        while pos < stop {
+            let col = pos % entry.line_bases;
+            let line_no = pos / entry.line_bases;
+            let file_offset = entry.offset + line_no * entry.line_width + col;
+            let bases_to_read = (entry.line_bases - col).min(stop - pos);
+
+            file.seek(SeekFrom::Start(file_offset))?;
+            let mut buf = vec![0u8; bases_to_read as usize];
+            file.read_exact(&mut buf)?;
+            result.extend_from_slice(&buf);
+
+            pos += bases_to_read;
+        }
+        Ok(result)
+    }
+}
+
+/// A source of reference sequence for mutation classification (`observed::classify_mutations`
+/// and `observed::Mutation::normalize`), implemented for both 2bit and indexed-FASTA references.
+pub trait ReferenceSource {
+    fn sequence(&self, chr: &str, start: usize, stop: usize) -> Result<String>;
+}
+
+impl ReferenceSource for TwoBitFile {
+    fn sequence(&self, chr: &str, start: usize, stop: usize) -> Result<String> {
+        Ok(self.sequence(chr, start, stop)?)
+    }
+}
+
+/// An indexed FASTA reference backed by rust-bio's `IndexedReader`, for users who only
+/// have a plain `.fa` + `.fai` and don't want to build a 2bit file first.
+/// `IndexedReader::fetch`/`read` take `&mut self`, so access is serialized behind a `RefCell`
+/// to fit the shared-reference `ReferenceSource` interface.
+///
+/// `.fa.gz` is rejected by `open`: `bio::io::fasta::IndexedReader` opens its path as a plain
+/// seekable file with no BGZF awareness, so a `.fa.gz` would fail or silently misread rather
+/// than actually work.
+pub struct IndexedFastaReference {
+    reader: RefCell<IndexedReader<File>>,
+}
+
+impl IndexedFastaReference {
+    pub fn open(path: &str) -> Result<Self> {
+        if path.ends_with(".gz") {
+            return Err(anyhow::anyhow!(
+                "{} is gzip-compressed, but IndexedFastaReference's indexed reader has no BGZF \
+                 awareness and can only seek a plain .fa file; decompress it first or build a \
+                 2bit file instead",
+                path
+            ));
+        }
+        let reader = IndexedReader::from_file(&path)
+            .map_err(|e| anyhow::anyhow!("failed to open indexed FASTA {}: {}", path, e))?;
+        Ok(Self {
+            reader: RefCell::new(reader),
+        })
+    }
+}
+
+impl ReferenceSource for IndexedFastaReference {
+    fn sequence(&self, chr: &str, start: usize, stop: usize) -> Result<String> {
+        let mut reader = self.reader.borrow_mut();
+        reader
+            .fetch(chr, start as u64, stop as u64)
+            .with_context(|| format!("failed to fetch {}:{}-{} from indexed FASTA", chr, start, stop))?;
+        let mut sequence = Vec::with_capacity(stop - start);
+        reader.read(&mut sequence)?;
+        Ok(String::from_utf8(sequence)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fasta_reference_reads_subsequence_across_lines() {
+        let fasta_path = "/tmp/unit_test_reference.fa";
+        let fai_path = "/tmp/unit_test_reference.fa.fai";
+        // two 10-base lines, so a [5, 15) query straddles the line break
+        std::fs::write(fasta_path, ">chr1\nACGTACGTAC\nGTACGTACGT\n").unwrap();
+        std::fs::write(fai_path, "chr1\t20\t6\t10\t11\n").unwrap();
+
+        let genome = FastaReference::open(fasta_path).unwrap();
+        let sequence = genome.sequence("chr1", 5, 15).unwrap();
+        assert_eq!(sequence, b"CGTACGTACG".to_vec());
+    }
+
+    #[test]
+    fn test_fasta_reference_rejects_unknown_contig() {
+        let fasta_path = "/tmp/unit_test_reference_unknown.fa";
+        let fai_path = "/tmp/unit_test_reference_unknown.fa.fai";
+        std::fs::write(fasta_path, ">chr1\nACGTACGTAC\n").unwrap();
+        std::fs::write(fai_path, "chr1\t10\t6\t10\t11\n").unwrap();
+
+        let genome = FastaReference::open(fasta_path).unwrap();
+        assert!(genome.sequence("chr2", 0, 5).is_err());
+    }
+
+    #[test]
+    fn test_fasta_reference_rejects_gzipped_input() {
+        // a `.fai`'s offsets are into the uncompressed sequence; seeking a `.fa.gz` by them
+        // would silently read garbage, so `open` must reject it instead.
+        let fasta_path = "/tmp/unit_test_reference.fa.gz";
+        let fai_path = "/tmp/unit_test_reference.fa.gz.fai";
+        std::fs::write(fasta_path, b"not actually gzipped, doesn't matter for this test").unwrap();
+        std::fs::write(fai_path, "chr1\t20\t6\t10\t11\n").unwrap();
+
+        assert!(FastaReference::open(fasta_path).is_err());
+    }
+
+    #[test]
+    fn test_indexed_fasta_reference_rejects_gzipped_input() {
+        let fasta_path = "/tmp/unit_test_indexed_reference.fa.gz";
+        assert!(IndexedFastaReference::open(fasta_path).is_err());
+    }
+}
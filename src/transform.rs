@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::io::{BufReader, BufWriter, Write};
 
@@ -21,116 +22,164 @@ struct GFF3Record {
     attributes: Option<String>,
 }
 
+/// Everything needed to build a `SeqAnnotation` for one transcript, accumulated while
+/// its exon/CDS children are collected from anywhere in the file.
+struct TranscriptBuilder {
+    chromosome: String,
+    range: Interval,
+    strand: Strand,
+    exons: Vec<Interval>,
+    coding_sequences: Vec<CDS>,
+}
+
+/// Parse a GFF3 file into one `SeqAnnotation` per transcript.
+///
+/// Real GFF3 files are not a strictly ordered tree: a `gene` can have several
+/// `mRNA`/`transcript` children, and their `exon`/`CDS` records can be interleaved with
+/// those of siblings. We therefore make two passes: first collect every row, then group
+/// exon/CDS records under their transcript by `Parent`, regardless of file order.
 pub fn transform_gff3_annotations(
     annotations_file: &str,
     filter_for_id: Option<&str>,
 ) -> Result<Vec<SeqAnnotation>> {
-    let mut result = Vec::new();
+    let rows = read_gff3_rows(annotations_file)?;
+    build_annotations_from_gff3_rows(&rows, filter_for_id)
+}
 
-    // dummy initialization values
-    let mut current_entity_name = String::new();
-    let mut current_chromosome = String::new();
-    let mut current_range = Interval::new(0, 1).expect("hardcoded");
-    let mut current_strand = Strand::Plus;
-    let mut current_exons = Vec::new();
-    let mut current_cdss = Vec::new();
+fn build_annotations_from_gff3_rows(
+    rows: &[GFF3Record],
+    filter_for_id: Option<&str>,
+) -> Result<Vec<SeqAnnotation>> {
+    let mut transcript_order = Vec::new();
+    let mut transcripts: HashMap<String, TranscriptBuilder> = HashMap::new();
 
-    let reader = get_reader(annotations_file)
-        .with_context(|| format!("failed to open file {} for reading", annotations_file))?;
-    let buf_reader = BufReader::new(reader);
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b'\t')
-        .comment(Some(b'#'))
-        .has_headers(false)
-        .from_reader(buf_reader);
-    for row_result in csv_reader.deserialize() {
-        let row: GFF3Record = row_result?;
-        let attributes = row.attributes.context("Missing attributes in GFF3 file")?;
-        match row.seq_type.as_str() {
-            "transcript" => {
-                if current_entity_name != "" {
-                    // if we have a previous transcript (or the transcript that we filter_for_id
-                    let anno = SeqAnnotation::new(
-                        current_entity_name.clone(),
-                        current_chromosome,
-                        current_range,
-                        current_strand,
-                        current_exons.clone(),
-                        current_cdss.clone(),
-                    );
-                    if let Some(id) = filter_for_id {
-                        if id == current_entity_name {
-                            result.push(anno);
-                        }
-                    } else {
-                        result.push(anno);
-                    }
-                }
-                current_entity_name = get_attribute(&attributes, "ID")
-                    .context("missing ID attribute")?
-                    .to_string();
-                current_chromosome = row.seq_id;
-                current_range = Interval::new(row.start - 1, row.end)?; // from 1-based to 0-based. End-exclusive
-                current_strand = row.strand.try_into()?;
-                current_exons.clear();
-                current_cdss.clear();
+    // first pass: every transcript/mRNA row defines one SeqAnnotation-to-be
+    for row in &rows {
+        if row.seq_type != "transcript" && row.seq_type != "mRNA" {
+            continue;
+        }
+        let attributes = row
+            .attributes
+            .as_deref()
+            .context("Missing attributes in GFF3 file")?;
+        let id = get_attribute(attributes, "ID")
+            .context("missing ID attribute")?
+            .to_string();
+        if let Some(filter_id) = filter_for_id {
+            if filter_id != id {
+                continue;
             }
+        }
+        transcript_order.push(id.clone());
+        transcripts.insert(
+            id,
+            TranscriptBuilder {
+                chromosome: row.seq_id.clone(),
+                range: Interval::new(row.start - 1, row.end)?, // from 1-based to 0-based, end-exclusive
+                strand: row.strand.try_into()?,
+                exons: Vec::new(),
+                coding_sequences: Vec::new(),
+            },
+        );
+    }
+
+    // second pass: attach exon/CDS rows to their transcript by Parent, wherever they appear
+    for row in &rows {
+        let attributes = match &row.attributes {
+            Some(attributes) => attributes,
+            None => continue,
+        };
+        match row.seq_type.as_str() {
             "exon" => {
-                let id = get_attribute(&attributes, "ID")
-                    .context("missing ID attribute in GFF3 file")?;
-                let parent = get_attribute(&attributes, "Parent")
+                let parent = get_attribute(attributes, "Parent")
                     .context("missing Parent attribute in GFF3 file")?;
-                if parent != current_entity_name {
-                    return Err(anyhow::anyhow!(
-                        "The gff3 file is not an ordered tree structure: Exon {} has parent {}",
-                        id,
-                        parent
-                    ));
+                if let Some(builder) = transcripts.get_mut(parent) {
+                    builder.exons.push(Interval::new(row.start - 1, row.end)?);
                 }
-                current_exons.push(Interval::new(row.start - 1, row.end)?);
             }
             "CDS" => {
-                let id = get_attribute(&attributes, "ID").context("missing ID attribute")?;
-                let parent =
-                    get_attribute(&attributes, "Parent").context("missing Parent attribute")?;
-                if parent != current_entity_name {
-                    return Err(anyhow::anyhow!(
-                        "The gff3 file is not an ordered tree structure: Exon {} has parent {}",
-                        id,
-                        parent
-                    ));
+                let parent = get_attribute(attributes, "Parent")
+                    .context("missing Parent attribute")?;
+                if let Some(builder) = transcripts.get_mut(parent) {
+                    let phase: Phase = row
+                        .phase
+                        .try_into()
+                        .context("CDS region without a proper phase")?;
+                    builder
+                        .coding_sequences
+                        .push(CDS::new(Interval::new(row.start - 1, row.end)?, phase));
                 }
-                let phase: Phase = row
-                    .phase
-                    .try_into()
-                    .context("CDS region without a proper phase")?;
-                current_cdss.push(CDS::new(Interval::new(row.start - 1, row.end)?, phase));
             }
             _ => {}
         }
     }
-    // finish off the last entry
-    if current_entity_name != "" {
-        //if we have a previous transcript
-        if let Some(id) = filter_for_id {
-            if id != current_entity_name {
-                return Ok(result);
-            }
-        }
-        let anno = SeqAnnotation::new(
-            current_entity_name,
-            current_chromosome,
-            current_range,
-            current_strand,
-            current_exons,
-            current_cdss,
-        );
-        result.push(anno);
-    }
 
+    let mut result = Vec::with_capacity(transcript_order.len());
+    for name in transcript_order {
+        let builder = transcripts.remove(&name).expect("just inserted above");
+        result.push(SeqAnnotation::new(
+            name,
+            builder.chromosome,
+            builder.range,
+            builder.strand,
+            builder.exons,
+            builder.coding_sequences,
+        ));
+    }
     Ok(result)
 }
 
+fn read_gff3_rows(annotations_file: &str) -> Result<Vec<GFF3Record>> {
+    let reader = get_reader(annotations_file)
+        .with_context(|| format!("failed to open file {} for reading", annotations_file))?;
+    read_gff3_rows_from(reader)
+}
+
+/// Like `read_gff3_rows`, but restricted to the genomic window `[start, stop)` on `chr`,
+/// using the BGZF+tabix fast path from `crate::io` when an index is available beside the
+/// file. Falls back to a full scan otherwise.
+fn read_gff3_rows_in_region(
+    annotations_file: &str,
+    chr: &str,
+    start: usize,
+    stop: usize,
+) -> Result<Vec<GFF3Record>> {
+    if crate::io::has_tabix_index(annotations_file) {
+        let reader = crate::io::get_region_reader(annotations_file, chr, start, stop)?;
+        read_gff3_rows_from(reader)
+    } else {
+        read_gff3_rows(annotations_file)
+    }
+}
+
+fn read_gff3_rows_from(reader: Box<dyn std::io::Read>) -> Result<Vec<GFF3Record>> {
+    let buf_reader = BufReader::new(reader);
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .comment(Some(b'#'))
+        .has_headers(false)
+        .from_reader(buf_reader);
+    let mut rows = Vec::new();
+    for row_result in csv_reader.deserialize() {
+        rows.push(row_result?);
+    }
+    Ok(rows)
+}
+
+/// Like `transform_gff3_annotations`, but for the common case where `filter_for_id`'s
+/// genomic window is already known (e.g. from a previously written `--genomic-regions`
+/// file): restricts the scan to that window via the tabix fast path when possible.
+pub fn transform_gff3_annotations_in_region(
+    annotations_file: &str,
+    chr: &str,
+    start: usize,
+    stop: usize,
+    filter_for_id: Option<&str>,
+) -> Result<Vec<SeqAnnotation>> {
+    let rows = read_gff3_rows_in_region(annotations_file, chr, start, stop)?;
+    build_annotations_from_gff3_rows(&rows, filter_for_id)
+}
+
 fn get_attribute<'a>(attr_str: &'a str, attribute_name: &str) -> Option<&'a str> {
     for attribute in attr_str.split(';') {
         if attribute.starts_with(attribute_name) {
@@ -145,6 +194,142 @@ fn get_attribute<'a>(attr_str: &'a str, attribute_name: &str) -> Option<&'a str>
     None
 }
 
+/// Parse a GTF file into one `SeqAnnotation` per transcript.
+///
+/// GTF uses `key "value";` attributes instead of GFF3's `key=value`, and identifies
+/// transcripts and their children via `transcript_id`/`gene_id` rather than `ID`/`Parent`.
+pub fn transform_gtf_annotations(
+    annotations_file: &str,
+    filter_for_id: Option<&str>,
+) -> Result<Vec<SeqAnnotation>> {
+    let rows = read_gff3_rows(annotations_file)?;
+
+    let mut transcript_order = Vec::new();
+    let mut transcripts: HashMap<String, TranscriptBuilder> = HashMap::new();
+
+    for row in &rows {
+        if row.seq_type != "transcript" {
+            continue;
+        }
+        let attributes = row
+            .attributes
+            .as_deref()
+            .context("Missing attributes in GTF file")?;
+        let id = get_gtf_attribute(attributes, "transcript_id")
+            .context("missing transcript_id attribute")?;
+        if let Some(filter_id) = filter_for_id {
+            if filter_id != id {
+                continue;
+            }
+        }
+        transcript_order.push(id.clone());
+        transcripts.insert(
+            id,
+            TranscriptBuilder {
+                chromosome: row.seq_id.clone(),
+                range: Interval::new(row.start - 1, row.end)?,
+                strand: row.strand.try_into()?,
+                exons: Vec::new(),
+                coding_sequences: Vec::new(),
+            },
+        );
+    }
+
+    for row in &rows {
+        let attributes = match &row.attributes {
+            Some(attributes) => attributes,
+            None => continue,
+        };
+        match row.seq_type.as_str() {
+            "exon" => {
+                let parent = get_gtf_attribute(attributes, "transcript_id")
+                    .context("missing transcript_id attribute in GTF file")?;
+                if let Some(builder) = transcripts.get_mut(&parent) {
+                    builder.exons.push(Interval::new(row.start - 1, row.end)?);
+                }
+            }
+            "CDS" => {
+                let parent = get_gtf_attribute(attributes, "transcript_id")
+                    .context("missing transcript_id attribute in GTF file")?;
+                if let Some(builder) = transcripts.get_mut(&parent) {
+                    let phase: Phase = row
+                        .phase
+                        .try_into()
+                        .context("CDS region without a proper phase")?;
+                    builder
+                        .coding_sequences
+                        .push(CDS::new(Interval::new(row.start - 1, row.end)?, phase));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut result = Vec::with_capacity(transcript_order.len());
+    for name in transcript_order {
+        let builder = transcripts.remove(&name).expect("just inserted above");
+        result.push(SeqAnnotation::new(
+            name,
+            builder.chromosome,
+            builder.range,
+            builder.strand,
+            builder.exons,
+            builder.coding_sequences,
+        ));
+    }
+    Ok(result)
+}
+
+/// Look up a `key "value";` pair in a GTF attribute string, stripping the surrounding quotes.
+fn get_gtf_attribute(attr_str: &str, attribute_name: &str) -> Option<String> {
+    for attribute in attr_str.split(';') {
+        let attribute = attribute.trim();
+        if attribute.is_empty() {
+            continue;
+        }
+        let mut parts = attribute.splitn(2, ' ');
+        let key = parts.next()?;
+        if key != attribute_name {
+            continue;
+        }
+        let value = parts.next()?.trim().trim_matches('"');
+        return Some(value.to_string());
+    }
+    None
+}
+
+/// Parse a gene annotation file, picking the GFF3 or GTF parser by file extension.
+pub fn transform_annotations(
+    annotations_file: &str,
+    filter_for_id: Option<&str>,
+) -> Result<Vec<SeqAnnotation>> {
+    let stripped = annotations_file.trim_end_matches(".gz");
+    if stripped.ends_with(".gtf") {
+        transform_gtf_annotations(annotations_file, filter_for_id)
+    } else {
+        transform_gff3_annotations(annotations_file, filter_for_id)
+    }
+}
+
+/// Like `transform_annotations`, but for the common case where `filter_for_id`'s genomic
+/// window is already known (e.g. resolved from a `--genomic-regions` file written by an
+/// earlier full `transform` run): takes the GFF3 tabix fast path when the input is indexed.
+/// `.gtf[.gz]` input has no region variant and always takes the full-scan path.
+pub fn transform_annotations_in_region(
+    annotations_file: &str,
+    chr: &str,
+    start: usize,
+    stop: usize,
+    filter_for_id: Option<&str>,
+) -> Result<Vec<SeqAnnotation>> {
+    let stripped = annotations_file.trim_end_matches(".gz");
+    if stripped.ends_with(".gtf") {
+        transform_gtf_annotations(annotations_file, filter_for_id)
+    } else {
+        transform_gff3_annotations_in_region(annotations_file, chr, start, stop, filter_for_id)
+    }
+}
+
 // this is not the best place to put it semantically, but the read() function is in the other crate
 // and this uses some utility functions from *this* crate.
 pub fn write_sequence_annotations_to_file(
@@ -239,6 +424,55 @@ chr2	test	CDS	38	40	.	+	1	bla=bla;Parent=transcript2;ID=cds3
         assert_eq!(a.coding_sequences[1].phase, Phase::One);
     }
 
+    #[test]
+    fn test_gff3_interleaved_transcripts_of_one_gene() {
+        // a gene with two mRNA children whose exons are interleaved in the file
+        let file = "/tmp/unit_test.transform_annotations.interleaved.gff3";
+        let anno_str = "chr1	test	gene	1	100	.	+	.	ID=gene1
+chr1	test	mRNA	10	90	.	+	.	ID=mrna1;Parent=gene1
+chr1	test	mRNA	10	90	.	+	.	ID=mrna2;Parent=gene1
+chr1	test	exon	20	30	.	+	.	ID=ex1;Parent=mrna1
+chr1	test	exon	20	30	.	+	.	ID=ex2;Parent=mrna2
+chr1	test	exon	35	40	.	+	.	ID=ex3;Parent=mrna1
+chr1	test	CDS	20	25	.	+	2	ID=cds1;Parent=mrna1
+chr1	test	CDS	38	40	.	+	1	ID=cds2;Parent=mrna1
+";
+        let mut fd = std::fs::File::create(file).unwrap();
+        fd.write_all(anno_str.as_bytes()).unwrap();
+        drop(fd);
+
+        let annos = transform_gff3_annotations(file, None).unwrap();
+        assert_eq!(annos.len(), 2);
+        assert_eq!(annos[0].name, "mrna1");
+        assert_eq!(annos[0].exons.len(), 2);
+        assert_eq!(annos[0].coding_sequences.len(), 2);
+        assert_eq!(annos[1].name, "mrna2");
+        assert_eq!(annos[1].exons.len(), 1);
+        assert_eq!(annos[1].coding_sequences.len(), 0);
+    }
+
+    #[test]
+    fn test_gtf_io() {
+        let file = "/tmp/unit_test.transform_annotations.gtf";
+        let anno_str = "chr1\ttest\tgene\t1\t100\t.\t+\t.\tgene_id \"gene1\";\n\
+                         chr1\ttest\ttranscript\t10\t90\t.\t+\t.\tgene_id \"gene1\"; transcript_id \"transcript1\";\n\
+                         chr1\ttest\texon\t20\t30\t.\t+\t.\tgene_id \"gene1\"; transcript_id \"transcript1\";\n\
+                         chr1\ttest\tCDS\t20\t25\t.\t+\t2\tgene_id \"gene1\"; transcript_id \"transcript1\";\n";
+        let mut fd = std::fs::File::create(file).unwrap();
+        fd.write_all(anno_str.as_bytes()).unwrap();
+        drop(fd);
+
+        let annos = transform_gtf_annotations(file, None).unwrap();
+        assert_eq!(annos.len(), 1);
+        let a = &annos[0];
+        assert_eq!(a.name, "transcript1");
+        assert_eq!(a.chr, "chr1");
+        assert_eq!(a.range, Interval::new(9, 90).unwrap());
+        assert_eq!(a.exons.len(), 1);
+        assert_eq!(a.coding_sequences.len(), 1);
+        assert_eq!(a.coding_sequences[0].phase, Phase::Two);
+    }
+
     #[test]
     fn test_region_file_io() {
         let file = "/tmp/unit_test.transform_annotations.regions";